@@ -0,0 +1,31 @@
+use std::path::Path;
+
+// With the `embed-font` feature, list every file of `font_png/` for `include_bytes!`
+fn main() {
+    // Otherwise cargo reruns the script whenever any file in the package changes
+    println!("cargo:rerun-if-changed=build.rs");
+    if std::env::var_os("CARGO_FEATURE_EMBED_FONT").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-changed=font_png");
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let font_dir = Path::new(&manifest_dir).join("font_png");
+    let mut files: Vec<_> = std::fs::read_dir(&font_dir)
+        .expect("embed-font needs the font_png directory")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    files.sort();
+
+    let mut out = String::from("pub static EMBEDDED_FONT: &[(&str, &[u8])] = &[\n");
+    for path in files {
+        let name = path.file_name().unwrap().to_string_lossy();
+        out.push_str(&format!("    ({:?}, include_bytes!({:?})),\n", name, path.display().to_string()));
+    }
+    out.push_str("];\n");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("embedded_font.rs"), out).unwrap();
+}
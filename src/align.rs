@@ -1,81 +1,70 @@
-use crate::define::{BOLD_PAD, SPACE_WIDTH, CHAR_HORIZON_PADDING, ITALIC_CHAR_HORIZON_PADDING};
-use crate::font_widths::FontWidths;
+use crate::define::{CHAR_HORIZON_PADDING, ITALIC_CHAR_HORIZON_PADDING};
+use crate::format_code::{FormatScanner, Style};
+use crate::metrics::FontMetrics;
 use crate::utils::find_closest;
 
-// Font widths data
-lazy_static::lazy_static! {
-    static ref FONT_WIDTHS: Vec<u8> = {
-        vec![12; 65536] // Default width for all characters
-    };
+pub fn get_char_width(metrics: &dyn FontMetrics, char: &str, bold: bool) -> i32 {
+    metrics.char_width(char, bold)
 }
 
-pub fn get_char_width(char: &str, bold: bool) -> i32 {
-    if char.is_empty() {
-        return 0;
-    }
-    
-    let char_code = char.chars().next().unwrap() as u32;
-    let font_widths = FontWidths::new();
-    let mut width = font_widths.get_width(char_code) as i32;
-    
-    if bold {
-        width += BOLD_PAD;
-    }
-    
-    width
+// Width of a space (plain or bold) in `metrics`
+fn space_width(metrics: &dyn FontMetrics, bold: bool) -> i32 {
+    metrics.char_width(" ", bold)
 }
 
-pub fn get_line_width(line: &str) -> i32 {
+pub fn get_line_width(metrics: &dyn FontMetrics, line: &str) -> i32 {
     if line.contains('\n') {
         panic!("Line contains newline; use get_lines_length instead");
     }
-    
+    get_styled_line_width(metrics, &mut FormatScanner::new(line))
+}
+
+// Width of the rest of `scanner`'s line; italic padding depends on the last character
+fn get_styled_line_width(metrics: &dyn FontMetrics, scanner: &mut FormatScanner) -> i32 {
     let mut width = 0;
-    let mut bold = false;
-    let mut _italic = false;
-    let mut fmt = false;
+    let mut last_italic = false;
     let mut length = 0;
     
-    for ch in line.chars() {
-        if ch == '§' {
-            fmt = true;
-            continue;
-        } else if fmt {
-            fmt = false;
-            match ch {
-                'l' => bold = true,
-                'o' => _italic = true,
-                'r' => {
-                    bold = false;
-                    _italic = false;
-                }
-                _ => {}
-            }
-            continue;
-        }
+    for token in scanner {
+        let Some(ch) = token.ch else { continue };
         length += 1;
-        width += get_char_width(&ch.to_string(), bold);
+        last_italic = token.style.italic;
+        width += get_char_width(metrics, &ch.to_string(), token.style.bold);
     }
     
     width += (length - 1).max(0) * CHAR_HORIZON_PADDING;
-    if _italic {
+    if last_italic {
         width += ITALIC_CHAR_HORIZON_PADDING;
     }
     width
 }
 
-pub fn get_lines_width(lines: &[String]) -> i32 {
-    lines.iter().map(|line| get_line_width(line)).max().unwrap_or(0)
+// Width of every line of `text`, with formatting carried over from one line to the next
+// like `TellRawSimulator` does
+pub fn get_text_line_widths(metrics: &dyn FontMetrics, text: &str) -> Vec<i32> {
+    let mut style = Style::default();
+    text.split('\n')
+        .map(|line| {
+            let mut scanner = FormatScanner::with_style(line, style);
+            let width = get_styled_line_width(metrics, &mut scanner);
+            style = scanner.style();
+            width
+        })
+        .collect()
+}
+
+pub fn get_lines_width(metrics: &dyn FontMetrics, lines: &[String]) -> i32 {
+    lines.iter().map(|line| get_line_width(metrics, line)).max().unwrap_or(0)
 }
 
-pub fn get_specific_length_spaces(length: i32) -> String {
-    get_specific_length_spaces_and_diff(length, 0).0
+pub fn get_specific_length_spaces(metrics: &dyn FontMetrics, length: i32) -> String {
+    get_specific_length_spaces_and_diff(metrics, length, 0).0
 }
 
-pub fn get_specific_length_spaces_and_diff(length: i32, prev_diff: i32) -> (String, i32) {
+pub fn get_specific_length_spaces_and_diff(metrics: &dyn FontMetrics, length: i32, prev_diff: i32) -> (String, i32) {
     let (solutions, min_diff) = find_closest(
-        SPACE_WIDTH + CHAR_HORIZON_PADDING,
-        SPACE_WIDTH + BOLD_PAD + CHAR_HORIZON_PADDING,
+        space_width(metrics, false) + CHAR_HORIZON_PADDING,
+        space_width(metrics, true) + CHAR_HORIZON_PADDING,
         length + prev_diff,
     );
     let (a, b, _) = solutions[0];
@@ -83,42 +72,26 @@ pub fn get_specific_length_spaces_and_diff(length: i32, prev_diff: i32) -> (Stri
     (s, min_diff as i32)
 }
 
-pub fn cut_by_length(line: &str, spaces: i32) -> Vec<String> {
+pub fn cut_by_length(metrics: &dyn FontMetrics, line: &str, spaces: i32) -> Vec<String> {
     let mut width = 0;
-    let spaces_width = spaces * SPACE_WIDTH + (spaces - 1).max(0) * CHAR_HORIZON_PADDING;
-    let mut bold = false;
-    let mut _italic = false;
-    let mut fmt = false;
+    let spaces_width = spaces * space_width(metrics, false) + (spaces - 1).max(0) * CHAR_HORIZON_PADDING;
     let mut outputs = Vec::new();
     let mut cached = String::new();
     
-    for ch in line.chars() {
-        if width >= spaces_width || ch == '\n' {
+    for token in FormatScanner::new(line) {
+        if width >= spaces_width || token.ch == Some('\n') {
             outputs.push(cached);
             cached = String::new();
             width = 0;
-            if ch == '\n' {
+            if token.ch == Some('\n') {
                 continue;
             }
         }
         
-        if ch == '§' {
-            fmt = true;
-        } else if fmt {
-            fmt = false;
-            match ch {
-                'l' => bold = true,
-                'o' => _italic = true,
-                'r' => {
-                    bold = false;
-                    _italic = false;
-                }
-                _ => {}
-            }
-        } else {
-            width += get_char_width(&ch.to_string(), bold) + CHAR_HORIZON_PADDING;
+        if let Some(ch) = token.ch {
+            width += get_char_width(metrics, &ch.to_string(), token.style.bold) + CHAR_HORIZON_PADDING;
         }
-        cached.push(ch);
+        cached.push_str(token.source);
     }
     
     if !cached.trim().is_empty() {
@@ -127,49 +100,49 @@ pub fn cut_by_length(line: &str, spaces: i32) -> Vec<String> {
     outputs
 }
 
-pub fn align_any_and_get_diff(text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
-    let width = get_line_width(text);
-    let spaces_left = spaces * SPACE_WIDTH - width;
+pub fn align_any_and_get_diff(metrics: &dyn FontMetrics, text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
+    let width = get_line_width(metrics, text);
+    let spaces_left = spaces * space_width(metrics, false) - width;
     if spaces_left < 0 {
         return (String::new(), spaces_left);
     }
-    get_specific_length_spaces_and_diff(spaces_left, prev_diff)
+    get_specific_length_spaces_and_diff(metrics, spaces_left, prev_diff)
 }
 
-pub fn align_any(text: &str, spaces: i32) -> String {
-    align_any_and_get_diff(text, spaces, 0).0
+pub fn align_any(metrics: &dyn FontMetrics, text: &str, spaces: i32) -> String {
+    align_any_and_get_diff(metrics, text, spaces, 0).0
 }
 
-pub fn align_left(text: &str, spaces: i32) -> String {
-    format!("{}{}", text, align_any(text, spaces))
+pub fn align_left(metrics: &dyn FontMetrics, text: &str, spaces: i32) -> String {
+    format!("{}{}", text, align_any(metrics, text, spaces))
 }
 
-pub fn align_left_and_get_diff(text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
-    let (t, diff) = align_any_and_get_diff(text, spaces, prev_diff);
+pub fn align_left_and_get_diff(metrics: &dyn FontMetrics, text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
+    let (t, diff) = align_any_and_get_diff(metrics, text, spaces, prev_diff);
     (format!("{}{}", text, t), diff)
 }
 
-pub fn align_right(text: &str, spaces: i32) -> String {
-    format!("{}{}", align_any(text, spaces), text)
+pub fn align_right(metrics: &dyn FontMetrics, text: &str, spaces: i32) -> String {
+    format!("{}{}", align_any(metrics, text, spaces), text)
 }
 
-pub fn align_right_and_get_diff(text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
-    let (t, diff) = align_any_and_get_diff(text, spaces, prev_diff);
+pub fn align_right_and_get_diff(metrics: &dyn FontMetrics, text: &str, spaces: i32, prev_diff: i32) -> (String, i32) {
+    let (t, diff) = align_any_and_get_diff(metrics, text, spaces, prev_diff);
     (format!("{}{}", t, text), diff)
 }
 
-pub fn align_center(text: &str, spaces: i32) -> String {
-    let textlen = get_line_width(text);
-    let rest = spaces * SPACE_WIDTH - textlen;
+pub fn align_center(metrics: &dyn FontMetrics, text: &str, spaces: i32) -> String {
+    let textlen = get_line_width(metrics, text);
+    let rest = spaces * space_width(metrics, false) - textlen;
     format!(
         "{}{}{}",
-        get_specific_length_spaces(rest / 2),
+        get_specific_length_spaces(metrics, rest / 2),
         text,
-        get_specific_length_spaces((rest as f64 / 2.0).round() as i32)
+        get_specific_length_spaces(metrics, (rest as f64 / 2.0).round() as i32)
     )
 }
 
-pub fn align_simple(args: &[AlignArg]) -> String {
+pub fn align_simple(metrics: &dyn FontMetrics, args: &[AlignArg]) -> String {
     let mut string = String::new();
     let mut diff = 0;
     
@@ -179,12 +152,12 @@ pub fn align_simple(args: &[AlignArg]) -> String {
                 string.push_str(text);
             }
             AlignArg::LeftAlign(text, spaces) => {
-                let (s, d) = align_left_and_get_diff(text, *spaces, -diff);
+                let (s, d) = align_left_and_get_diff(metrics, text, *spaces, -diff);
                 string.push_str(&s);
                 diff = d;
             }
             AlignArg::RightAlign(text, spaces) => {
-                let (s, d) = align_right_and_get_diff(text, *spaces, -diff);
+                let (s, d) = align_right_and_get_diff(metrics, text, *spaces, -diff);
                 string.push_str(&s);
                 diff = d;
             }
@@ -200,31 +173,33 @@ pub enum AlignArg {
     RightAlign(String, i32),
 }
 
-pub fn yield_chars_and_length(line: &str) -> Vec<(char, i32)> {
+pub fn yield_chars_and_length(metrics: &dyn FontMetrics, line: &str) -> Vec<(char, i32)> {
     let mut result = Vec::new();
     let mut width = 0;
-    let mut bold = false;
-    let mut _italic = false;
-    let mut fmt = false;
-    
-    for ch in line.chars() {
-        if ch == '§' {
-            fmt = true;
-        } else if fmt {
-            fmt = false;
-            match ch {
-                'l' => bold = true,
-                'o' => _italic = true,
-                'r' => {
-                    bold = false;
-                    _italic = false;
-                }
-                _ => {}
-            }
-        } else {
-            width += get_char_width(&ch.to_string(), bold) + ITALIC_CHAR_HORIZON_PADDING;
+
+    for token in FormatScanner::new(line) {
+        if let Some(ch) = token.ch {
+            width += get_char_width(metrics, &ch.to_string(), token.style.bold) + ITALIC_CHAR_HORIZON_PADDING;
         }
-        result.push((ch, width));
+        // Every source character of the token, format codes included, ends at the same width
+        result.extend(token.source.chars().map(|c| (c, width)));
     }
     result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::width_table::WidthTable;
+
+    #[test]
+    fn format_codes_take_no_width() {
+        let table = WidthTable::from_widths(10, []);
+        let widths: Vec<i32> = yield_chars_and_length(&table, "a§lb§§§zc").into_iter().map(|(_, w)| w).collect();
+        let step = 10 + ITALIC_CHAR_HORIZON_PADDING;
+        let bold = table.char_width("b", true) + ITALIC_CHAR_HORIZON_PADDING;
+        // a, §l, b, §§, §z
+        let expected = [step, step, step, step + bold, step + 2 * bold, step + 2 * bold, step + 3 * bold, step + 3 * bold, step + 4 * bold];
+        assert_eq!(widths, expected);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::io::{Error, ErrorKind};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::command::RenderLayout;
+use crate::font::RuneFont;
+use crate::render::{scale_layout, MissingGlyphError, SimulateOptions, TellRawSimulator};
+
+// Animated previews of obfuscated (`§k`) text
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "image/gif",
+            AnimationFormat::Apng => "image/apng",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    pub format: AnimationFormat,
+    pub frames: u32,
+    pub frame_delay_ms: u16,
+    // Fixed seed for reproducible frames; a time based seed when `None`
+    pub seed: Option<u64>,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            format: AnimationFormat::Gif,
+            frames: 20,
+            frame_delay_ms: 50,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AnimationError {
+    #[error(transparent)]
+    MissingGlyph(#[from] MissingGlyphError),
+    #[error("Failed to encode animation: {0}")]
+    Encode(#[from] Error),
+}
+
+fn encode_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> Error {
+    Error::other(e)
+}
+
+// Looping GIF or APNG of equally sized frames
+pub fn encode_frames(frames: Vec<RgbaImage>, format: AnimationFormat, frame_delay_ms: u16) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    match format {
+        AnimationFormat::Gif => {
+            let mut encoder = GifEncoder::new(&mut buf);
+            encoder.set_repeat(Repeat::Infinite).map_err(encode_error)?;
+            let delay = Delay::from_numer_denom_ms(frame_delay_ms as u32, 1);
+            encoder
+                .encode_frames(frames.into_iter().map(|img| Frame::from_parts(img, 0, 0, delay)))
+                .map_err(encode_error)?;
+        }
+        AnimationFormat::Apng => {
+            let Some(first) = frames.first() else {
+                return Err(Error::new(ErrorKind::InvalidInput, "no frames to encode"));
+            };
+            let mut encoder = png::Encoder::new(&mut buf, first.width(), first.height());
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(frames.len() as u32, 0).map_err(encode_error)?;
+            encoder.set_frame_delay(frame_delay_ms, 1000).map_err(encode_error)?;
+            let mut writer = encoder.write_header().map_err(encode_error)?;
+            for frame in &frames {
+                writer.write_image_data(frame.as_raw()).map_err(encode_error)?;
+            }
+            writer.finish().map_err(encode_error)?;
+        }
+    }
+    Ok(buf)
+}
+
+fn time_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+pub fn render_animation(
+    font: RuneFont,
+    text: &str,
+    layout: RenderLayout,
+    options: Option<SimulateOptions>,
+    animation: &AnimationOptions,
+) -> Result<Vec<u8>, AnimationError> {
+    let mut simulator = TellRawSimulator::new(font, options.unwrap_or_default());
+    let seed = animation.seed.unwrap_or_else(time_seed);
+    let frames = simulator
+        .render_frames(text, animation.frames.max(1), seed)?
+        .into_iter()
+        .map(|frame| scale_layout(frame, layout))
+        .collect();
+    Ok(encode_frames(frames, animation.format, animation.frame_delay_ms)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::MissingGlyphPolicy;
+
+    fn simulator() -> TellRawSimulator {
+        TellRawSimulator::new(RuneFont::new("font_png"), SimulateOptions::default())
+    }
+
+    #[test]
+    fn frames_depend_only_on_the_seed() {
+        let text = "§kobfuscated§r text";
+        let frames = simulator().render_frames(text, 4, 7).unwrap();
+        assert_eq!(frames, simulator().render_frames(text, 4, 7).unwrap());
+        assert_ne!(frames, simulator().render_frames(text, 4, 8).unwrap());
+
+        let width = simulator().render(text).width();
+        assert!(frames.iter().all(|frame| frame.width() == width));
+    }
+
+    #[test]
+    fn wide_glyphs_are_swapped_too() {
+        let plain = simulator().render("中文");
+        let frames = simulator().render_frames("§k中文", 8, 1).unwrap();
+        assert!(frames.iter().all(|frame| frame.width() == plain.width()));
+        assert!(frames.iter().any(|frame| *frame != plain));
+    }
+
+    #[test]
+    fn missing_glyphs_fail_under_the_error_policy() {
+        let mut font = RuneFont::new("font_png");
+        font.set_missing_glyph_policy(MissingGlyphPolicy::Error);
+        let mut simulator = TellRawSimulator::new(font, SimulateOptions::default());
+        let err = simulator.render_frames("a§k\u{E500}", 2, 1).unwrap_err();
+        assert_eq!(err.missing, vec!['\u{E500}']);
+    }
+}
@@ -0,0 +1,31 @@
+use mcbe_text_impact::font::RuneFont;
+use mcbe_text_impact::font_source::FontStack;
+use mcbe_text_impact::WidthTable;
+
+// Usage: gen-widths <output.json> [font pack ...]
+// Packs are listed highest priority first and default to `font_png`.
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(output) = args.next() else {
+        eprintln!("Usage: gen-widths <output.json> [font pack ...]");
+        std::process::exit(2);
+    };
+    let mut paths: Vec<String> = args.collect();
+    if paths.is_empty() {
+        paths.push("font_png".to_string());
+    }
+
+    let stack = match FontStack::from_paths(&paths) {
+        Ok(stack) => stack,
+        Err(e) => {
+            eprintln!("Error: Cannot open font: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let table = WidthTable::generate(&RuneFont::from_stack(stack));
+    if let Err(e) = table.save(std::path::Path::new(&output)) {
+        eprintln!("Error: Cannot write '{}': {}", output, e);
+        std::process::exit(1);
+    }
+    println!("Wrote {} width runs to {}", table.runs.len(), output);
+}
@@ -0,0 +1,201 @@
+use image::{Rgba, RgbaImage};
+use crate::align::get_char_width;
+use crate::define::{CHAR_HORIZON_PADDING, GAME_PIXEL, GLYPH_HEIGHT, ITALIC_CHAR_HORIZON_PADDING};
+use crate::font::RuneFont;
+use crate::format_code::{FormatScanner, Style, Token};
+use crate::metrics::FontMetrics;
+use crate::render::{MissingGlyphError, RenderOutput, SimulateOptions, TellRawSimulator};
+use crate::utils::blend_image;
+
+// Messages as they appear in the chat HUD: wrapped to the panel width, one translucent
+// row per line, newest at the bottom. Sizes are logical units like `SimulateOptions`.
+#[derive(Debug, Clone)]
+pub struct ChatOptions {
+    // Panel width including `padding` on both sides
+    pub width: i32,
+    pub line_height: i32,
+    // Space between the panel edge and the text
+    pub padding: i32,
+    // Older lines scroll out above this many
+    pub max_lines: usize,
+    // Output pixels per game pixel at the font's own cell size, so HD packs stay sharp
+    pub gui_scale: u32,
+    pub background: (u8, u8, u8, u8),
+}
+
+impl Default for ChatOptions {
+    fn default() -> Self {
+        Self {
+            width: 320 * GAME_PIXEL,
+            line_height: 9 * GAME_PIXEL,
+            padding: 2 * GAME_PIXEL,
+            max_lines: 10,
+            gui_scale: 2,
+            background: (0, 0, 0, 128),
+        }
+    }
+}
+
+// Split `message` into lines no wider than `width`, breaking after words where possible
+// and inside words that do not fit on a line of their own. Each line starts with the
+// format codes in effect where it was split off.
+pub fn wrap_message(metrics: &dyn FontMetrics, message: &str, width: i32) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut style = Style::default();
+
+    for line in message.split('\n') {
+        let tokens: Vec<Token> = FormatScanner::with_style(line, style).collect();
+        let mut start = 0;
+        loop {
+            let prefix = style.codes();
+            // Width of the characters so far, without the padding after the last one
+            let (mut chars, mut sum) = (0, 0);
+            let mut end = start;
+            let mut last_space = None;
+            while end < tokens.len() {
+                if let Some(ch) = tokens[end].ch {
+                    let char_width = get_char_width(metrics, &ch.to_string(), tokens[end].style.bold);
+                    let italic = if tokens[end].style.italic { ITALIC_CHAR_HORIZON_PADDING } else { 0 };
+                    if chars > 0 && sum + chars * CHAR_HORIZON_PADDING + char_width + italic > width {
+                        break;
+                    }
+                    chars += 1;
+                    sum += char_width;
+                    if ch == ' ' {
+                        last_space = Some(end);
+                    }
+                }
+                end += 1;
+            }
+            // The spaces a line breaks at are dropped
+            let (mut line_end, mut next) = match last_space {
+                Some(space) if end < tokens.len() && space > start => (space, space + 1),
+                _ => (end, end),
+            };
+            if end < tokens.len() {
+                while line_end > start && tokens[line_end - 1].ch == Some(' ') {
+                    line_end -= 1;
+                }
+                while next < tokens.len() && tokens[next].ch == Some(' ') {
+                    next += 1;
+                }
+            }
+            let text: String = tokens[start..line_end].iter().map(|t| t.source).collect();
+            out.push(format!("{}{}", prefix, text));
+            if let Some(last) = tokens[..next].last() {
+                style = last.style;
+            }
+            start = next;
+            if start >= tokens.len() {
+                break;
+            }
+        }
+    }
+    out
+}
+
+// Chat panel showing `messages` (oldest first) at `options.gui_scale`
+pub fn render_chat(
+    mut font: RuneFont,
+    messages: &[String],
+    options: &ChatOptions,
+    simulate: Option<SimulateOptions>,
+) -> Result<RenderOutput, MissingGlyphError> {
+    font.set_cell_size(options.gui_scale.max(1) * font.cell_size() / GAME_PIXEL as u32);
+    let wrap_width = options.width - 2 * options.padding;
+    let lines: Vec<String> = messages
+        .iter()
+        .flat_map(|message| wrap_message(&font, message, wrap_width))
+        .collect();
+    let visible = &lines[lines.len().saturating_sub(options.max_lines)..];
+
+    let (width, line_height) = (font.to_px(options.width) as u32, font.to_px(options.line_height) as u32);
+    let (padding, text_top) = (font.to_px(options.padding), font.to_px((options.line_height - GLYPH_HEIGHT) / 2));
+    let (r, g, b, a) = options.background;
+
+    let mut simulator = TellRawSimulator::new(font, SimulateOptions { background: None, ..simulate.unwrap_or_default() });
+    let mut mat = RgbaImage::new(width, visible.len() as u32 * line_height);
+    let mut missing: Vec<char> = Vec::new();
+    let mut failed = false;
+
+    for (i, line) in visible.iter().enumerate() {
+        let top = i as u32 * line_height;
+        for y in top..top + line_height {
+            for x in 0..width {
+                mat.put_pixel(x, y, Rgba([r, g, b, a]));
+            }
+        }
+        let output = match simulator.render_with_report(line) {
+            Ok(output) => output,
+            // Keep going so the error lists every missing glyph
+            Err(e) => {
+                failed = true;
+                RenderOutput { image: RgbaImage::new(0, 0), missing: e.missing }
+            }
+        };
+        for c in output.missing {
+            if !missing.contains(&c) {
+                missing.push(c);
+            }
+        }
+        blend_image(&mut mat, &output.image, padding as i64, (top as i32 + text_top) as i64);
+    }
+
+    if failed {
+        return Err(MissingGlyphError { missing });
+    }
+    Ok(RenderOutput { image: mat, missing })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::get_line_width;
+
+    fn font() -> RuneFont {
+        RuneFont::new("font_png")
+    }
+
+    #[test]
+    fn lines_break_after_words() {
+        let font = font();
+        let width = get_line_width(&font, "hello world") - 1;
+        assert_eq!(wrap_message(&font, "hello world", width), vec!["hello", "world"]);
+        assert_eq!(wrap_message(&font, "hello world", width + 1), vec!["hello world"]);
+    }
+
+    #[test]
+    fn runs_of_spaces_are_dropped_at_a_break() {
+        let font = font();
+        let width = get_line_width(&font, "§lhello") + 4;
+        assert_eq!(wrap_message(&font, "§lhello world  foo", width), vec!["§lhello", "§lworld", "§lfoo"]);
+        assert_eq!(wrap_message(&font, "a   b", get_line_width(&font, "a  ")), vec!["a", "b"]);
+        assert_eq!(wrap_message(&font, "§lhello world  foo", 60).iter().filter(|l| l.starts_with("§l ")).count(), 0);
+    }
+
+    #[test]
+    fn long_words_break_inside() {
+        let font = font();
+        let width = get_line_width(&font, "aaaa");
+        assert_eq!(wrap_message(&font, "aaaaaaaaaa", width), vec!["aaaa", "aaaa", "aa"]);
+    }
+
+    #[test]
+    fn lines_start_with_the_format_in_effect() {
+        let font = font();
+        let width = get_line_width(&font, "§lhello world") - 1;
+        assert_eq!(wrap_message(&font, "§a§lhello world", width), vec!["§a§lhello", "§a§lworld"]);
+        assert_eq!(wrap_message(&font, "§oab\ncd§r\nef", i32::MAX), vec!["§oab", "§ocd§r", "ef"]);
+    }
+
+    #[test]
+    fn panel_scales_with_the_font_cell() {
+        let messages = vec!["hello".to_string()];
+        let options = ChatOptions::default();
+        let normal = render_chat(font(), &messages, &options, None).unwrap().image;
+        let mut hd = font();
+        hd.set_cell_size(64);
+        let hd = render_chat(hd, &messages, &options, None).unwrap().image;
+        assert_eq!((hd.width(), hd.height()), (2 * normal.width(), 2 * normal.height()));
+    }
+}
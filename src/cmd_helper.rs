@@ -1,5 +1,159 @@
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, Map};
 use std::collections::HashMap;
+use crate::lang::LangFile;
+use crate::rawtext::{Component, RawText, TranslateWith};
+use crate::selector::{Selector, SelectorBase, World};
+
+// What a `score` component shows when the holder has no score on the objective
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingScorePolicy {
+    // Nothing is printed, like the game
+    #[default]
+    Empty,
+    Zero,
+    Placeholder(String),
+}
+
+// Substitution data used while resolving rawtext components
+#[derive(Debug, Clone, Default)]
+pub struct TellrawContext {
+    pub selectors_sub: HashMap<String, String>,
+    pub scores_sub: HashMap<String, HashMap<String, i32>>,
+    pub lang: LangFile,
+    // Mock entities used for selectors not listed in `selectors_sub`
+    pub world: Option<World>,
+    // Player the message is shown to, used for `"name":"*"` scores
+    pub viewer: Option<String>,
+    pub missing_score: MissingScorePolicy,
+}
+
+impl TellrawContext {
+    pub fn new(
+        selectors_sub: HashMap<String, String>,
+        scores_sub: HashMap<String, HashMap<String, i32>>,
+        lang: LangFile,
+    ) -> Self {
+        Self {
+            selectors_sub,
+            scores_sub,
+            lang,
+            ..Default::default()
+        }
+    }
+
+    // Names a selector stands for: its `selectors_sub` entry, else its matches in `world`.
+    // Without an executor in the world, `@s` is the player the message is shown to.
+    fn selected_names(&self, selector: &str) -> Vec<String> {
+        if let Some(sub) = self.selectors_sub.get(selector) {
+            return sub.split(", ").filter(|n| !n.is_empty()).map(|n| n.to_string()).collect();
+        }
+        let Ok(parsed) = Selector::parse(selector) else { return Vec::new() };
+        if let Some(world) = &self.world {
+            let names: Vec<String> = parsed.select(world).iter().map(|e| e.name.clone()).collect();
+            if !names.is_empty() {
+                return names;
+            }
+        }
+        let has_executor = self.world.as_ref().is_some_and(|w| w.executor.is_some());
+        match parsed.base {
+            SelectorBase::Executor | SelectorBase::Initiator if !has_executor => self.viewer.iter().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Score holder name for a `score` component: `*` is the viewer, selectors pick their first match
+    fn score_holder(&self, name: &str) -> Option<String> {
+        if name == "*" {
+            return self.viewer.clone();
+        }
+        if !name.starts_with('@') && !self.selectors_sub.contains_key(name) {
+            return Some(name.to_string());
+        }
+        self.selected_names(name).into_iter().next()
+    }
+
+    fn score_value(&self, holder: &str, objective: &str) -> Option<i32> {
+        if let Some(&value) = self.scores_sub.get(objective).and_then(|scb_data| scb_data.get(holder)) {
+            return Some(value);
+        }
+        self.world
+            .as_ref()?
+            .entities
+            .iter()
+            .find(|e| e.name == holder)
+            .and_then(|e| e.scores.get(objective).copied())
+    }
+
+    fn score(&self, name: &str, objective: &str) -> String {
+        let value = self
+            .score_holder(name)
+            .and_then(|holder| self.score_value(&holder, objective));
+        match (value, &self.missing_score) {
+            (Some(value), _) => value.to_string(),
+            (None, MissingScorePolicy::Empty) => String::new(),
+            (None, MissingScorePolicy::Zero) => "0".to_string(),
+            (None, MissingScorePolicy::Placeholder(p)) => p.clone(),
+        }
+    }
+
+    fn selector(&self, selector: &str) -> String {
+        // Plain player names print as themselves
+        if !selector.starts_with('@') && !self.selectors_sub.contains_key(selector) {
+            return selector.to_string();
+        }
+        self.selected_names(selector).join(", ")
+    }
+}
+
+fn text_element(text: String) -> Value {
+    Value::Object(Map::from_iter([("text".to_string(), Value::String(text))]))
+}
+
+impl TellrawContext {
+    // Flatten a typed rawtext tree into one formatted string.
+    // Children are concatenated in order, so § codes carry over into the following
+    // elements exactly as they do in game.
+    pub fn resolve(&self, rawtext: &RawText) -> String {
+        rawtext.rawtext.iter().map(|c| self.resolve_component(c)).collect()
+    }
+
+    pub fn resolve_component(&self, component: &Component) -> String {
+        match component {
+            Component::Text { text } => text.clone(),
+            Component::Score { score } => self.score(&score.name, &score.objective),
+            Component::Selector { selector } => self.selector(selector),
+            Component::RawText { rawtext } => rawtext.iter().map(|c| self.resolve_component(c)).collect(),
+            Component::Translate { translate, with } => {
+                let args = match with {
+                    Some(TranslateWith::Strings(strings)) => strings.clone(),
+                    Some(TranslateWith::RawText(rawtext)) => rawtext
+                        .rawtext
+                        .iter()
+                        .map(|c| self.resolve_component(c))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                self.lang.translate(translate, &args)
+            }
+        }
+    }
+}
+
+// Flatten an untyped rawtext tree; malformed elements contribute nothing
+pub fn resolve_rawtext(element: &Value, ctx: &TellrawContext) -> String {
+    match element {
+        Value::String(s) => s.clone(),
+        Value::Array(arr) => arr.iter().map(|v| resolve_rawtext(v, ctx)).collect(),
+        Value::Object(obj) if obj.get("text").is_none() && obj.get("rawtext").is_some_and(|v| v.is_array()) => {
+            resolve_rawtext(&obj["rawtext"], ctx)
+        }
+        _ => Component::parse(element, "")
+            .map(|c| ctx.resolve_component(&c))
+            .unwrap_or_default(),
+    }
+}
 
 // Translate tellraw JSON with substitutions
 pub fn translate_tellraw(
@@ -7,44 +161,99 @@ pub fn translate_tellraw(
     selectors_sub: &HashMap<String, String>,
     scores_sub: &HashMap<String, HashMap<String, i32>>,
 ) -> Value {
+    translate_tellraw_with_lang(jsonc, selectors_sub, scores_sub, &LangFile::new())
+}
+
+// Translate tellraw JSON with substitutions, resolving `translate` keys against a `.lang` file
+pub fn translate_tellraw_with_lang(
+    jsonc: &mut Value,
+    selectors_sub: &HashMap<String, String>,
+    scores_sub: &HashMap<String, HashMap<String, i32>>,
+    lang: &LangFile,
+) -> Value {
+    let ctx = TellrawContext::new(selectors_sub.clone(), scores_sub.clone(), lang.clone());
+    translate_tellraw_with_context(jsonc, &ctx)
+}
+
+// Replace every score / selector / translate / nested rawtext element with its resolved text
+pub fn translate_tellraw_with_context(jsonc: &mut Value, ctx: &TellrawContext) -> Value {
     if let Some(rawtext) = jsonc.get_mut("rawtext") {
         if let Some(rawtext_array) = rawtext.as_array_mut() {
             for element in rawtext_array.iter_mut() {
-                if let Some(score) = element.get("score") {
-                    if let Some(score_obj) = score.as_object() {
-                        if let (Some(name), Some(objective)) = (
-                            score_obj.get("name").and_then(|v| v.as_str()),
-                            score_obj.get("objective").and_then(|v| v.as_str()),
-                        ) {
-                            let replacement = if let Some(scb_data) = scores_sub.get(objective) {
-                                if let Some(&value) = scb_data.get(name) {
-                                    Value::String(value.to_string())
-                                } else {
-                                    Value::String(String::new())
-                                }
-                            } else {
-                                Value::String(String::new())
-                            };
-                            
-                            *element = Value::Object(Map::from_iter(vec![
-                                ("text".to_string(), replacement)
-                            ].into_iter()));
-                        }
-                    }
-                } else if let Some(selector) = element.get("selector") {
-                    if let Some(selector_str) = selector.as_str() {
-                        let replacement = selectors_sub.get(selector_str)
-                            .map(|s| Value::String(s.clone()))
-                            .unwrap_or_else(|| Value::String(String::new()));
-                        
-                        *element = Value::Object(Map::from_iter(vec![
-                            ("text".to_string(), replacement)
-                        ].into_iter()));
-                    }
+                // Malformed elements are left untouched
+                match Component::parse(element, "") {
+                    Ok(Component::Text { .. }) | Err(_) => {}
+                    Ok(component) => *element = text_element(ctx.resolve_component(&component)),
                 }
             }
         }
     }
-    
+
     jsonc.clone()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::selector::MockEntity;
+
+    fn player(name: &str, kills: Option<i32>) -> MockEntity {
+        let mut entity = MockEntity::player(name);
+        entity.scores.extend(kills.map(|k| ("kills".to_string(), k)));
+        entity
+    }
+
+    fn resolve(ctx: &TellrawContext, rawtext: Value) -> String {
+        resolve_rawtext(&rawtext, ctx)
+    }
+
+    fn score(name: &str) -> Value {
+        json!({"score": {"name": name, "objective": "kills"}})
+    }
+
+    #[test]
+    fn star_is_the_viewer() {
+        let mut ctx = TellrawContext::default();
+        ctx.scores_sub.insert("kills".to_string(), HashMap::from([("Alex".to_string(), 3)]));
+        assert_eq!(resolve(&ctx, json!([score("*")])), "");
+        ctx.viewer = Some("Alex".to_string());
+        assert_eq!(resolve(&ctx, json!([score("*")])), "3");
+    }
+
+    #[test]
+    fn executor_without_a_world_is_the_viewer() {
+        let mut ctx = TellrawContext { viewer: Some("Alex".to_string()), ..Default::default() };
+        ctx.scores_sub.insert("kills".to_string(), HashMap::from([("Alex".to_string(), 3)]));
+        assert_eq!(resolve(&ctx, json!([score("*"), "|", score("@s"), "|", {"selector": "@s"}])), "3|3|Alex");
+
+        ctx.world = Some(World { entities: vec![player("Kai", Some(7))], executor: Some("Kai".to_string()), seed: 0 });
+        assert_eq!(resolve(&ctx, json!([score("@s"), "|", {"selector": "@s"}])), "7|Kai");
+    }
+
+    #[test]
+    fn selectors_and_scores_agree() {
+        let world = World { entities: vec![player("Steve", Some(1)), player("Alex", Some(2))], ..Default::default() };
+        let mut ctx = TellrawContext { world: Some(world), ..Default::default() };
+        let text = json!([{"selector": "@p"}, " has ", score("@p")]);
+        assert_eq!(resolve(&ctx, text.clone()), "Steve has 1");
+
+        ctx.selectors_sub.insert("@p".to_string(), "Alex".to_string());
+        assert_eq!(resolve(&ctx, text), "Alex has 2");
+
+        // A selector the world matches nobody for falls back to `selectors_sub`
+        ctx.selectors_sub.insert("@a[tag=admin]".to_string(), "Alex".to_string());
+        assert_eq!(resolve(&ctx, json!([{"selector": "@a[tag=admin]"}, " ", score("@a[tag=admin]")])), "Alex 2");
+        assert_eq!(resolve(&ctx, json!([{"selector": "@e[type=zombie]"}, score("@e[type=zombie]")])), "");
+    }
+
+    #[test]
+    fn missing_scores_follow_the_policy() {
+        let mut ctx = TellrawContext::default();
+        let text = json!(["[", score("Nobody"), "]"]);
+        assert_eq!(resolve(&ctx, text.clone()), "[]");
+        ctx.missing_score = MissingScorePolicy::Zero;
+        assert_eq!(resolve(&ctx, text.clone()), "[0]");
+        ctx.missing_score = MissingScorePolicy::Placeholder("?".to_string());
+        assert_eq!(resolve(&ctx, text), "[?]");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,233 @@
+use serde::Serialize;
+use thiserror::Error;
+use crate::cmd_helper::TellrawContext;
+use crate::rawtext::{RawText, RawTextError};
+
+// Parse `.mcfunction` style command lines carrying a chat or title message
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleChannel {
+    Title,
+    Subtitle,
+    Actionbar,
+}
+
+// Where on screen a message ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderLayout {
+    Chat,
+    Title,
+    Subtitle,
+    Actionbar,
+}
+
+impl RenderLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderLayout::Chat => "chat",
+            RenderLayout::Title => "title",
+            RenderLayout::Subtitle => "subtitle",
+            RenderLayout::Actionbar => "actionbar",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Tellraw { target: String, message: RawText },
+    Titleraw { target: String, channel: TitleChannel, message: RawText },
+    Say { message: String },
+    Me { message: String },
+}
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Empty command line")]
+    Empty,
+    #[error("Unsupported command '{0}', expected tellraw, titleraw, say or me")]
+    UnknownCommand(String),
+    #[error("Missing {0}")]
+    MissingArgument(&'static str),
+    #[error("Unbalanced brackets or quotes in target '{0}'")]
+    BadTarget(String),
+    #[error("titleraw action '{0}' has no message to render")]
+    UnsupportedTitleAction(String),
+    #[error(transparent)]
+    RawText(#[from] RawTextError),
+}
+
+// Split the first argument off `s`, keeping `[...]` selector arguments and quoted names intact
+fn split_target(s: &str) -> Result<(String, &str), CommandError> {
+    let s = s.trim_start();
+    if s.is_empty() {
+        return Err(CommandError::MissingArgument("target"));
+    }
+
+    if let Some(rest) = s.strip_prefix('"') {
+        let end = rest.find('"').ok_or_else(|| CommandError::BadTarget(s.to_string()))?;
+        return Ok((rest[..end].to_string(), &rest[end + 1..]));
+    }
+
+    // Only selector arguments nest; a `{` outside them starts the message
+    let mut depth = 0;
+    let mut in_quote = false;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '"' => in_quote = !in_quote,
+            '[' if !in_quote => depth += 1,
+            ']' if !in_quote => {
+                if depth == 0 {
+                    return Err(CommandError::BadTarget(s.to_string()));
+                }
+                depth -= 1;
+            }
+            c if (c.is_whitespace() || c == '{') && depth == 0 && !in_quote => {
+                if i == 0 {
+                    return Err(CommandError::MissingArgument("target"));
+                }
+                return Ok((s[..i].to_string(), &s[i..]));
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 || in_quote {
+        return Err(CommandError::BadTarget(s.to_string()));
+    }
+    Ok((s.to_string(), ""))
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    }
+}
+
+fn parse_message(s: &str) -> Result<RawText, CommandError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(CommandError::MissingArgument("raw json message"));
+    }
+    Ok(RawText::from_json(s)?)
+}
+
+// Whether `line` is written as a command (`/tellraw ...`, `say ...`) rather than a bare message
+pub fn is_command_line(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with('/') {
+        return true;
+    }
+    let (name, _) = split_word(line);
+    matches!(name.to_ascii_lowercase().as_str(), "tellraw" | "titleraw" | "say" | "me")
+}
+
+pub fn parse_command(line: &str) -> Result<Command, CommandError> {
+    let line = line.trim();
+    let line = line.strip_prefix('/').unwrap_or(line);
+    let (name, rest) = split_word(line);
+    if name.is_empty() {
+        return Err(CommandError::Empty);
+    }
+
+    match name.to_ascii_lowercase().as_str() {
+        "tellraw" => {
+            let (target, rest) = split_target(rest)?;
+            Ok(Command::Tellraw { target, message: parse_message(rest)? })
+        }
+        "titleraw" => {
+            let (target, rest) = split_target(rest)?;
+            let (action, rest) = split_word(rest);
+            let channel = match action.to_ascii_lowercase().as_str() {
+                "title" => TitleChannel::Title,
+                "subtitle" => TitleChannel::Subtitle,
+                "actionbar" => TitleChannel::Actionbar,
+                "" => return Err(CommandError::MissingArgument("titleraw action")),
+                _ => return Err(CommandError::UnsupportedTitleAction(action.to_string())),
+            };
+            Ok(Command::Titleraw { target, channel, message: parse_message(rest)? })
+        }
+        "say" => Ok(Command::Say { message: rest.trim().to_string() }),
+        "me" => Ok(Command::Me { message: rest.trim().to_string() }),
+        _ => Err(CommandError::UnknownCommand(name.to_string())),
+    }
+}
+
+impl Command {
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Command::Tellraw { target, .. } | Command::Titleraw { target, .. } => Some(target),
+            Command::Say { .. } | Command::Me { .. } => None,
+        }
+    }
+
+    pub fn layout(&self) -> RenderLayout {
+        match self {
+            Command::Titleraw { channel: TitleChannel::Title, .. } => RenderLayout::Title,
+            Command::Titleraw { channel: TitleChannel::Subtitle, .. } => RenderLayout::Subtitle,
+            Command::Titleraw { channel: TitleChannel::Actionbar, .. } => RenderLayout::Actionbar,
+            _ => RenderLayout::Chat,
+        }
+    }
+
+    // Formatted text as the game displays it; `sender` names the executor of `say` / `me`
+    pub fn to_text(&self, ctx: &TellrawContext, sender: &str) -> String {
+        match self {
+            Command::Tellraw { message, .. } | Command::Titleraw { message, .. } => ctx.resolve(message),
+            Command::Say { message } => format!("[{}] {}", sender, message),
+            Command::Me { message } => format!("* {} {}", sender, message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_target_plain_and_selector() {
+        assert_eq!(split_target("@a {}").unwrap(), ("@a".to_string(), " {}"));
+        assert_eq!(split_target("  Steve rest").unwrap(), ("Steve".to_string(), " rest"));
+        let (target, rest) = split_target("@a[scores={kills=1..}, name=\"a b\"] {}").unwrap();
+        assert_eq!(target, "@a[scores={kills=1..}, name=\"a b\"]");
+        assert_eq!(rest, " {}");
+    }
+
+    #[test]
+    fn split_target_without_space_before_message() {
+        assert_eq!(split_target("@a{\"rawtext\":[]}").unwrap(), ("@a".to_string(), "{\"rawtext\":[]}"));
+        assert_eq!(split_target("@p[r=5]{}").unwrap(), ("@p[r=5]".to_string(), "{}"));
+    }
+
+    #[test]
+    fn split_target_quoted_name() {
+        assert_eq!(split_target("\"Alex Smith\" {}").unwrap(), ("Alex Smith".to_string(), " {}"));
+    }
+
+    #[test]
+    fn split_target_errors() {
+        assert!(matches!(split_target("   "), Err(CommandError::MissingArgument("target"))));
+        assert!(matches!(split_target("{}"), Err(CommandError::MissingArgument("target"))));
+        assert!(matches!(split_target("@a[r=5"), Err(CommandError::BadTarget(_))));
+        assert!(matches!(split_target("@a]"), Err(CommandError::BadTarget(_))));
+        assert!(matches!(split_target("\"unterminated"), Err(CommandError::BadTarget(_))));
+    }
+
+    #[test]
+    fn parse_command_without_space() {
+        let command = parse_command(r#"/tellraw @a{"rawtext":[{"text":"hi"}]}"#).unwrap();
+        assert_eq!(command.target(), Some("@a"));
+        assert_eq!(command.layout(), RenderLayout::Chat);
+    }
+
+    #[test]
+    fn command_lines_are_recognized() {
+        assert!(is_command_line("/tellraw @a {}"));
+        assert!(is_command_line("  TitleRaw @s title {}"));
+        assert!(is_command_line("say hi"));
+        assert!(!is_command_line("\"hello\""));
+        assert!(!is_command_line("{\"rawtext\":[]}"));
+        assert!(!is_command_line("saying hi"));
+    }
+}
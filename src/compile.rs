@@ -0,0 +1,155 @@
+use thiserror::Error;
+use crate::rawtext::{Component, RawText};
+
+// Compile § formatted text into tellraw rawtext.
+// `{score:<objective>:<name>}` and `{selector:<selector>}` become score / selector
+// components. Any other brace is kept as text; only a literal `{score:` or `{selector:`
+// needs escaping, written `{{score:` / `{{selector:`.
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CompileError {
+    #[error("Unterminated placeholder starting at character {0}")]
+    Unterminated(usize),
+    #[error("Malformed placeholder '{0}', expected {{score:<objective>:<name>}} or {{selector:<selector>}}")]
+    Malformed(String),
+}
+
+fn push_text(out: &mut Vec<Component>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    // Merge with the previous text element to keep the output minimal
+    if let Some(Component::Text { text: last }) = out.last_mut() {
+        last.push_str(text);
+    } else {
+        out.push(Component::text(text));
+    }
+}
+
+// Index just past the `}` closing the placeholder that starts at `start`
+fn placeholder_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &ch) in chars.iter().enumerate().skip(start) {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_placeholder(body: &str) -> Result<Component, CompileError> {
+    if let Some(rest) = body.strip_prefix("score:") {
+        match rest.split_once(':') {
+            Some((objective, name)) if !objective.is_empty() && !name.is_empty() => {
+                Ok(Component::score(name, objective))
+            }
+            _ => Err(CompileError::Malformed(format!("{{{}}}", body))),
+        }
+    } else if let Some(selector) = body.strip_prefix("selector:") {
+        if selector.is_empty() {
+            return Err(CompileError::Malformed(format!("{{{}}}", body)));
+        }
+        Ok(Component::selector(selector))
+    } else {
+        Err(CompileError::Malformed(format!("{{{}}}", body)))
+    }
+}
+
+fn starts_with_at(chars: &[char], i: usize, prefix: &str) -> bool {
+    prefix.chars().enumerate().all(|(k, p)| chars.get(i + k) == Some(&p))
+}
+
+fn starts_placeholder(chars: &[char], i: usize) -> bool {
+    chars.get(i) == Some(&'{') && (starts_with_at(chars, i + 1, "score:") || starts_with_at(chars, i + 1, "selector:"))
+}
+
+pub fn compile_rawtext(text: &str) -> Result<RawText, CompileError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut buf = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '{' && starts_placeholder(&chars, i + 1) {
+            // Escaped placeholder, kept as text with a single opening brace
+            i += 1;
+            let end = placeholder_end(&chars, i).unwrap_or(chars.len());
+            buf.extend(&chars[i..end]);
+            i = end;
+        } else if starts_placeholder(&chars, i) {
+            let end = placeholder_end(&chars, i).ok_or(CompileError::Unterminated(i))?;
+            let body: String = chars[i + 1..end - 1].iter().collect();
+            push_text(&mut out, &buf);
+            buf.clear();
+            out.push(parse_placeholder(&body)?);
+            i = end;
+        } else {
+            buf.push(ch);
+            i += 1;
+        }
+    }
+    push_text(&mut out, &buf);
+
+    Ok(RawText::new(out))
+}
+
+// Formatted text to a `{"rawtext":[...]}` string ready to paste into a command
+pub fn compile_tellraw(text: &str) -> Result<String, CompileError> {
+    Ok(compile_rawtext(text)?.to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> RawText {
+        let compiled = compile_rawtext(text).unwrap();
+        assert_eq!(RawText::from_json(&compile_tellraw(text).unwrap()).unwrap(), compiled);
+        compiled
+    }
+
+    #[test]
+    fn plain_text_is_kept_verbatim() {
+        for text in ["§l§aHello (pad3)", "a}}b{{c", "{\"json\": {}}", "{score}", "}{", ""] {
+            let expected = if text.is_empty() { vec![] } else { vec![Component::text(text)] };
+            assert_eq!(round_trip(text), RawText::new(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn placeholders_become_components() {
+        assert_eq!(
+            round_trip("§6{score:kills:*} by {selector:@a[scores={kills=1..}]}!"),
+            RawText::new(vec![
+                Component::text("§6"),
+                Component::score("*", "kills"),
+                Component::text(" by "),
+                Component::selector("@a[scores={kills=1..}]"),
+                Component::text("!"),
+            ])
+        );
+    }
+
+    #[test]
+    fn only_placeholders_need_escaping() {
+        assert_eq!(
+            round_trip("{{score:kills:*} {{selector:@p}}"),
+            RawText::new(vec![Component::text("{score:kills:*} {selector:@p}}")])
+        );
+    }
+
+    #[test]
+    fn bad_placeholders_are_errors() {
+        assert_eq!(compile_rawtext("a{score:kills:*"), Err(CompileError::Unterminated(1)));
+        assert_eq!(compile_rawtext("{score:kills}"), Err(CompileError::Malformed("{score:kills}".into())));
+        assert_eq!(compile_rawtext("{selector:}"), Err(CompileError::Malformed("{selector:}".into())));
+    }
+}
@@ -1,4 +1,10 @@
 pub const BOLD_PAD: i32 = 2;
 pub const SPACE_WIDTH: i32 = 12;
 pub const CHAR_HORIZON_PADDING: i32 = 4;
-pub const ITALIC_CHAR_HORIZON_PADDING: i32 = 8;
\ No newline at end of file
+pub const ITALIC_CHAR_HORIZON_PADDING: i32 = 8;
+pub const MISSING_GLYPH_WIDTH: i32 = 16;
+// Glyph cell size the constants above are expressed in
+pub const LOGICAL_CELL: u32 = 32;
+pub const GLYPH_HEIGHT: i32 = 31;
+// Logical units per game pixel; a glyph cell is 8 game pixels wide
+pub const GAME_PIXEL: i32 = 4;
\ No newline at end of file
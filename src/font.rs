@@ -1,7 +1,11 @@
-use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 use image::{ImageBuffer, Rgba, RgbaImage};
-use crate::define::SPACE_WIDTH;
+use serde::{Deserialize, Serialize};
+use crate::define::{BOLD_PAD, CHAR_HORIZON_PADDING, GLYPH_HEIGHT, LOGICAL_CELL, MISSING_GLYPH_WIDTH, SPACE_WIDTH};
+use crate::font_source::{open_source, page_file_name, DirSource, FontStack, GlyphSource};
+use crate::glyph_cache::{page_budget, GlyphCache, GlyphKey, Page};
+use crate::utils::blend_image;
 
 pub type RgbaMatrix = ImageBuffer<Rgba<u8>, Vec<u8>>;
 
@@ -9,11 +13,17 @@ pub type RgbaMatrix = ImageBuffer<Rgba<u8>, Vec<u8>>;
 pub struct Font {
     pub mat: RgbaMatrix,
     pub colored: bool,
+    // The font has no glyph for this character and `mat` is the stand-in
+    pub missing: bool,
 }
 
 impl Font {
     pub fn new(mat: RgbaMatrix, colored: bool) -> Self {
-        Self { mat, colored }
+        Self { mat, colored, missing: false }
+    }
+
+    pub fn missing(mat: RgbaMatrix) -> Self {
+        Self { mat, colored: false, missing: true }
     }
 
     pub fn width(&self) -> u32 {
@@ -24,81 +34,276 @@ impl Font {
         self.mat.height()
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         Self {
             mat: self.mat.clone(),
             colored: self.colored,
+            missing: self.missing,
         }
     }
 }
 
 pub trait FontMaker {
-    fn get_font(&mut self, rune: &str, fmt: u32) -> Font;
+    fn get_font(&self, rune: &str, fmt: u32) -> Arc<Font>;
 }
 
 impl FontMaker for RuneFont {
-    fn get_font(&mut self, rune: &str, fmt: u32) -> Font {
+    fn get_font(&self, rune: &str, fmt: u32) -> Arc<Font> {
         self.get_font(rune, fmt)
     }
 }
 
+// How characters outside the Basic Multilingual Plane (emoji, ...) are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AstralPolicy {
+    // Draw the missing-glyph box
+    #[default]
+    MissingGlyph,
+    // Draw the UTF-16 surrogate pair from `glyph_D8.png`..`glyph_DF.png` if the pack
+    // ships those pages, otherwise the missing-glyph box
+    Surrogates,
+}
+
+// What is drawn for characters the font has no glyph for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingGlyphPolicy {
+    // The placeholder box the game shows
+    #[default]
+    Tofu,
+    // A `SPACE_WIDTH` gap
+    Space,
+    // Draw the box, but `render_with_report` fails
+    Error,
+}
+
+pub fn is_astral(rune: &str) -> bool {
+    rune.chars().next().is_some_and(|c| c as u32 > 0xFFFF)
+}
+
+// Zero-width and format (Cf) characters: joiners, bidi marks, variation selectors, tags, ...
+// They take no space when the font has no glyph for them and are never reported missing.
+pub fn is_invisible(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xAD | 0x600..=0x605 | 0x61C | 0x6DD | 0x70F | 0x180E | 0x200B..=0x200F | 0x202A..=0x202E
+            | 0x2060..=0x2064 | 0x2066..=0x206F | 0xFE00..=0xFE0F | 0xFEFF | 0xFFF9..=0xFFFB
+            | 0x110BD | 0x1D173..=0x1D17A | 0xE0001 | 0xE0020..=0xE007F | 0xE0100..=0xE01EF
+    )
+}
+
+// Hollow box shown for characters the font cannot draw, at the logical cell size
+pub fn missing_glyph() -> RgbaImage {
+    let w = MISSING_GLYPH_WIDTH as u32;
+    let (top, bottom) = (3, 28);
+    let mut mat = RgbaImage::new(w, GLYPH_HEIGHT as u32);
+    for y in top..bottom {
+        for x in 0..w {
+            if y < top + 2 || y >= bottom - 2 || x < 2 || x >= w - 2 {
+                mat.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+    }
+    mat
+}
+
+// Basic Latin sheet the client prefers over `glyph_00.png`
+pub const ASCII_SHEET: &str = "default8.png";
+
+// Glyph pages are kept at their native resolution (`cell = width / 16`) and each glyph is
+// scaled to `cell_size`, the pixel size of one glyph cell in the output. Layout constants
+// (`SPACE_WIDTH`, paddings, ...) are logical units for a `LOGICAL_CELL` cell; `to_px` and
+// `to_logical` convert between the two.
+//
+// Decoded pages and glyphs live in the process-wide `GlyphCache` of the source, so a
+// `RuneFont` is cheap to clone and can be shared across threads.
+#[derive(Clone)]
 pub struct RuneFont {
-    root_dir: String,
-    cached_group: HashMap<u32, (RgbaImage, bool)>,
-    cached_rune: HashMap<(String, u32), Font>,
+    source: Arc<dyn GlyphSource>,
+    cache: Arc<GlyphCache>,
+    cell_size: u32,
+    use_ascii_sheet: bool,
+    astral_policy: AstralPolicy,
+    missing_policy: MissingGlyphPolicy,
 }
 
 impl RuneFont {
     pub fn new(root_dir: &str) -> Self {
+        Self::from_source(Box::new(DirSource::new(Path::new(root_dir))))
+    }
+
+    pub fn from_source(source: Box<dyn GlyphSource>) -> Self {
+        let cache = GlyphCache::for_source(&source.name(), &source.revision());
         Self {
-            root_dir: root_dir.to_string(),
-            cached_group: HashMap::new(),
-            cached_rune: HashMap::new(),
+            source: Arc::from(source),
+            cache,
+            cell_size: LOGICAL_CELL,
+            use_ascii_sheet: true,
+            astral_policy: AstralPolicy::default(),
+            missing_policy: MissingGlyphPolicy::default(),
         }
     }
 
+    // Load from a `.mcpack` / `.zip`, an unpacked pack root or a glyph directory
+    pub fn from_pack(path: &str) -> std::io::Result<Self> {
+        Ok(Self::from_source(open_source(Path::new(path))?))
+    }
+
+    pub fn from_stack(stack: FontStack) -> Self {
+        Self::from_source(Box::new(stack))
+    }
+
+    // The same font with a cache of its own that keeps pages but no finished glyphs, for
+    // one-off passes over every glyph
+    pub(crate) fn with_private_cache(&self) -> Self {
+        Self {
+            cache: Arc::new(GlyphCache::with_budget(page_budget(), 0)),
+            ..self.clone()
+        }
+    }
+
+    // The vanilla font compiled into the crate, no files needed at runtime
+    #[cfg(feature = "embed-font")]
+    pub fn embedded() -> Self {
+        Self::from_source(Box::new(crate::font_source::EmbeddedSource))
+    }
+
+    // Name of the pack that supplies glyph page `page`
+    pub fn page_origin(&self, page: u32) -> Option<String> {
+        self.source.origin(&page_file_name(page))
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    // Output pixels per glyph cell; 32 by default, larger values keep HD pages crisp
+    pub fn set_cell_size(&mut self, cell_size: u32) {
+        self.cell_size = cell_size.max(1);
+    }
+
+    // Largest cell among the source's glyph pages, read from the PNG headers only
+    pub fn native_cell_size(&self) -> u32 {
+        (0..=0xFF)
+            .filter_map(|page| self.source.read(&page_file_name(page)))
+            .filter_map(|bytes| image::io::Reader::new(std::io::Cursor::new(bytes)).with_guessed_format().ok()?.into_dimensions().ok())
+            .map(|(w, _)| w / 16)
+            .max()
+            .unwrap_or(LOGICAL_CELL)
+    }
+
+    // Logical units to output pixels
+    pub fn to_px(&self, logical: i32) -> i32 {
+        logical * self.cell_size as i32 / LOGICAL_CELL as i32
+    }
+
+    // Output pixels to logical units, rounded
+    pub fn to_logical(&self, px: i32) -> i32 {
+        (px * LOGICAL_CELL as i32 + self.cell_size as i32 / 2) / self.cell_size as i32
+    }
+
+    pub fn glyph_height(&self) -> u32 {
+        self.to_px(GLYPH_HEIGHT) as u32
+    }
+
+    // Advance of a glyph in logical units, independent of the pack's resolution
+    pub fn logical_width(&self, rune: &str, fmt: u32) -> i32 {
+        let width = self.get_font(rune, fmt).width() as i32;
+        self.to_logical(width)
+    }
+
+    // Nearest-neighbour scale from a logical cell image to the output cell size
+    fn scale_logical(&self, img: &RgbaImage) -> RgbaImage {
+        if self.cell_size == LOGICAL_CELL {
+            return img.clone();
+        }
+        let w = self.to_px(img.width() as i32).max(1) as u32;
+        let h = self.to_px(img.height() as i32).max(1) as u32;
+        image::imageops::resize(img, w, h, image::imageops::FilterType::Nearest)
+    }
+
+    pub fn set_astral_policy(&mut self, policy: AstralPolicy) {
+        self.astral_policy = policy;
+    }
+
+    pub fn missing_glyph_policy(&self) -> MissingGlyphPolicy {
+        self.missing_policy
+    }
+
+    pub fn set_missing_glyph_policy(&mut self, policy: MissingGlyphPolicy) {
+        self.missing_policy = policy;
+    }
+
+    // Stand-in for a character without a glyph, following the missing-glyph policy
+    fn missing_font(&self) -> Font {
+        match self.missing_policy {
+            MissingGlyphPolicy::Space => {
+                Font::missing(RgbaImage::new(self.to_px(SPACE_WIDTH) as u32, self.glyph_height()))
+            }
+            MissingGlyphPolicy::Tofu | MissingGlyphPolicy::Error => Font::missing(self.scale_logical(&missing_glyph())),
+        }
+    }
+
+    // Page, row and column of the first UTF-16 code unit, so astral characters map to
+    // their high surrogate instead of a page number beyond `glyph_FF.png`
     pub fn rune_to_idx(rune: &str) -> (u32, u32, u32) {
-        let chars: Vec<char> = rune.chars().collect();
-        if chars.is_empty() {
+        let Some(code) = rune.encode_utf16().next() else {
             return (0, 0, 0);
-        }
-        let code = chars[0] as u32;
+        };
+        let code = code as u32;
         (code >> 8, (code & 0xF0) >> 4, code & 0xF)
     }
 
     pub fn rune_to_raw_idx(rune: &str) -> u32 {
         let code = rune.encode_utf16().collect::<Vec<u16>>();
-        if code.len() >= 1 { code[code.len() - 1] as u32 } else { 0 }
+        if !code.is_empty() { code[code.len() - 1] as u32 } else { 0 }
     }
 
+    // Surrogate code units are not characters and map to a space
     pub fn idx_to_rune(group: u32, row: u32, col: u32) -> String {
-        let idx = group * (16 * 16) + row * 16 + col;
-        let code = idx as u16;
-        char::from_u32(code as u32).unwrap_or(' ').to_string()
+        let idx = (group & 0xFF) * (16 * 16) + (row & 0xF) * 16 + (col & 0xF);
+        char::from_u32(idx).unwrap_or(' ').to_string()
     }
 
-    fn get_group(&mut self, group_idx: u32) -> Option<(RgbaImage, bool)> {
-        if let Some(cached) = self.cached_group.get(&group_idx) {
-            return Some(cached.clone());
+    fn get_group(&self, group_idx: u32) -> Option<Arc<Page>> {
+        if let Some(cached) = self.cache.page(group_idx) {
+            return Some(cached);
         }
 
-        let file_path = format!("{}/glyph_{:02X}.png", self.root_dir, group_idx);
-        if !Path::new(&file_path).exists() {
+        let Some(bytes) = self.source.read(&page_file_name(group_idx)) else {
             // Create empty font image if file not found
-            let empty_img = RgbaImage::new(512, 512);
-            self.cached_group.insert(group_idx, (empty_img.clone(), false));
-            return Some((empty_img, false));
-        }
+            let empty = Page { image: RgbaImage::new(16, 16), colored: false };
+            return Some(self.cache.insert_page(group_idx, empty));
+        };
 
-        let img = image::open(&file_path).ok()?;
+        let img = image::load_from_memory(&bytes).ok()?;
         let rgba_img = img.to_rgba8();
-        let resized = image::imageops::resize(&rgba_img, 512, 512, image::imageops::FilterType::Nearest);
-        
+        if rgba_img.width() < 16 || rgba_img.height() < rgba_img.width() {
+            return None;
+        }
+
         // Check if colored
-        let colored = !self.is_grayscale(&resized);
-        
-        self.cached_group.insert(group_idx, (resized.clone(), colored));
-        Some((resized, colored))
+        let colored = !self.is_grayscale(&rgba_img);
+
+        Some(self.cache.insert_page(group_idx, Page { image: rgba_img, colored }))
+    }
+
+    // Decode the pages the source has into the shared cache, stopping as soon as the page
+    // cache has to evict to make room. Returns the number of pages loaded.
+    pub fn preload(&self) -> usize {
+        let mut loaded = 0;
+        let evictions = self.cache.page_evictions();
+        for page in (0..=0xFF).filter(|&page| self.has_page(page)) {
+            if self.get_group(page).is_some() {
+                loaded += 1;
+            }
+            if self.cache.page_evictions() != evictions {
+                break;
+            }
+        }
+        self.get_ascii_sheet();
+        loaded
     }
 
     fn is_grayscale(&self, img: &RgbaImage) -> bool {
@@ -115,10 +320,10 @@ impl RuneFont {
         let (x1, x2) = if let Some((x1, _, x2, _)) = bbox {
             (x1, x2)
         } else {
-            (0, SPACE_WIDTH as u32)
+            (0, self.to_px(SPACE_WIDTH) as u32)
         };
-        
-        image::imageops::crop_imm(square, x1, 0, x2 - x1, 31).to_image()
+
+        image::imageops::crop_imm(square, x1, 0, x2 - x1, self.glyph_height()).to_image()
     }
 
     fn get_bbox(&self, img: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
@@ -145,59 +350,339 @@ impl RuneFont {
         }
     }
 
-    pub fn get_font(&mut self, rune: &str, fmt: u32) -> Font {
-        let key = (rune.to_string(), fmt);
-        if let Some(cached) = self.cached_rune.get(&key) {
-            return cached.clone();
+    // `default8.png` if the source has one; loaded on first use
+    fn get_ascii_sheet(&self) -> Option<Arc<RgbaImage>> {
+        self.cache.ascii_sheet(|| {
+            self.source
+                .read(ASCII_SHEET)
+                .and_then(|bytes| image::load_from_memory(&bytes).ok())
+                .map(|img| img.to_rgba8())
+        })
+    }
+
+    // Glyph from the 16x16 `default8.png` sheet, scaled to the output glyph cell. The whole
+    // cell is kept so descenders in its bottom row are not cut off.
+    // Like the client, the advance is measured from the cell's left edge to its last
+    // opaque column and the space is fixed at `SPACE_WIDTH`; empty cells are not covered.
+    fn get_ascii_glyph(&self, rune: &str) -> Option<RgbaImage> {
+        if !self.use_ascii_sheet {
+            return None;
+        }
+        let code = rune.chars().next()? as u32;
+        if code >= 256 {
+            return None;
+        }
+        let (out_cell, space) = (self.cell_size, self.to_px(SPACE_WIDTH) as u32);
+        let sheet = self.get_ascii_sheet()?;
+        let cell = sheet.width() / 16;
+        if cell == 0 || sheet.height() < cell * 16 {
+            return None;
+        }
+
+        if code == ' ' as u32 {
+            return Some(RgbaImage::new(space, self.glyph_height()));
+        }
+
+        let cropped = image::imageops::crop_imm(sheet.as_ref(), (code % 16) * cell, (code / 16) * cell, cell, cell).to_image();
+        let last_col = (0..cell)
+            .rev()
+            .find(|&x| (0..cell).any(|y| cropped.get_pixel(x, y)[3] > 0))?;
+        let scaled = image::imageops::resize(&cropped, out_cell, out_cell, image::imageops::FilterType::Nearest);
+        let width = ((last_col + 1) * out_cell).div_ceil(cell);
+        Some(image::imageops::crop_imm(&scaled, 0, 0, width, out_cell).to_image())
+    }
+
+    // Use `default8.png` for the code points it covers (on by default when present)
+    pub fn set_ascii_sheet(&mut self, enabled: bool) {
+        self.use_ascii_sheet = enabled;
+    }
+
+    fn get_glyph(&self, rune: &str) -> Option<Font> {
+        if let Some(mat) = self.get_ascii_glyph(rune) {
+            return Some(Font::new(mat, false));
         }
 
         let (g, r, c) = Self::rune_to_idx(rune);
-        let page = self.get_group(g);
-        
-        let font = if let Some((png, colored)) = page {
-            let posx = c * 32;
-            let posy = r * 32;
-            let cropped = image::imageops::crop_imm(&png, posx, posy, 32, 31).to_image();
-            let tighted = self.tight_font(&cropped);
-            
-            let mut font = Font::new(tighted, colored);
-            
-            if fmt != 0 && !font.colored {
-                font = font.clone();
-                if fmt & 0x100 != 0 { // FMT_Obfuscated
-                    for pixel in font.mat.pixels_mut() {
-                        pixel[0] = 1;
-                        pixel[1] = 1;
-                        pixel[2] = 1;
-                    }
-                }
-                if fmt & 0x200 != 0 { // FMT_Bold
-                    let h = font.mat.height();
-                    let w = font.mat.width();
-                    let pad = 2;
-                    let mut new_mat = RgbaImage::new(w + pad, h);
-                    
-                    for y in 0..h {
-                        for x in 0..w {
-                            let pixel = font.mat.get_pixel(x, y);
-                            for off in 0..pad {
-                                if x + off < w + pad {
-                                    new_mat.put_pixel(x + off, y, *pixel);
-                                }
-                            }
-                        }
-                    }
-                    font.mat = new_mat;
-                }
+        let invisible = rune.chars().next().is_some_and(is_invisible);
+        let blank = Font::new(RgbaImage::new(0, self.glyph_height()), false);
+        if !self.has_page(g) {
+            return Some(if invisible { blank } else { self.missing_font() });
+        }
+        let page = self.get_group(g)?;
+        let font = self.get_cell(&page, r, c);
+        // Whitespace is meant to be blank, any other empty cell has no glyph
+        if font.missing && invisible {
+            return Some(blank);
+        }
+        if font.missing && !rune.chars().all(char::is_whitespace) {
+            return Some(self.missing_font());
+        }
+        Some(Font::new(font.mat, font.colored))
+    }
+
+    // Crop a cell at the page's native size and scale it to the output cell; empty cells
+    // come back as a `SPACE_WIDTH` gap marked missing
+    fn get_cell(&self, page: &Page, row: u32, col: u32) -> Font {
+        let native = page.image.width() / 16;
+        let mut cropped = image::imageops::crop_imm(&page.image, col * native, row * native, native, native).to_image();
+        if native != self.cell_size {
+            cropped = image::imageops::resize(&cropped, self.cell_size, self.cell_size, image::imageops::FilterType::Nearest);
+        }
+        let tighted = self.tight_font(&cropped);
+        Font {
+            missing: self.get_bbox(&cropped).is_none(),
+            ..Font::new(tighted, page.colored)
+        }
+    }
+
+    // Formatted like any other glyph; with `AstralPolicy::Surrogates` each half is formatted
+    // on its own, so the width is the sum of both halves as a client walking UTF-16 units
+    // would draw them
+    fn get_astral_glyph(&self, rune: &str, fmt: u32) -> Font {
+        let missing = self.format_glyph(self.missing_font(), fmt);
+        if self.astral_policy == AstralPolicy::MissingGlyph {
+            return missing;
+        }
+
+        let mut halves = Vec::new();
+        for unit in rune.chars().next().map(|c| c.to_string()).unwrap_or_default().encode_utf16() {
+            let unit = unit as u32;
+            if !self.has_page(unit >> 8) {
+                return missing;
             }
-            
-            font
+            let Some(page) = self.get_group(unit >> 8) else {
+                return missing;
+            };
+            let half = self.get_cell(&page, (unit & 0xF0) >> 4, unit & 0xF);
+            if half.missing {
+                return missing;
+            }
+            halves.push(self.format_glyph(half, fmt));
+        }
+
+        let pad = self.to_px(CHAR_HORIZON_PADDING) as u32;
+        let width = halves.iter().map(|f| f.width()).sum::<u32>() + pad * (halves.len() as u32 - 1);
+        let mut mat = RgbaImage::new(width, self.glyph_height());
+        let mut x = 0;
+        for half in &halves {
+            image::imageops::overlay(&mut mat, &half.mat, x as i64, 0);
+            x += half.width() + pad;
+        }
+        Font::new(mat, halves.iter().any(|f| f.colored))
+    }
+
+    fn has_page(&self, page: u32) -> bool {
+        self.source.origin(&page_file_name(page)).is_some()
+    }
+
+    pub fn get_font(&self, rune: &str, fmt: u32) -> Arc<Font> {
+        let key = GlyphKey {
+            rune: rune.to_string(),
+            fmt,
+            cell_size: self.cell_size,
+            ascii_sheet: self.use_ascii_sheet,
+            astral_policy: self.astral_policy,
+            missing_policy: self.missing_policy,
+        };
+        if let Some(cached) = self.cache.glyph(&key) {
+            return cached;
+        }
+
+        let invisible = rune.chars().next().is_some_and(is_invisible);
+        let font = if is_astral(rune) && !invisible {
+            self.get_astral_glyph(rune, fmt)
+        } else if let Some(font) = self.get_glyph(rune) {
+            self.format_glyph(font, fmt)
         } else {
-            // Fallback to space
-            self.get_font(" ", fmt)
+            // The page exists but could not be decoded
+            self.missing_font()
         };
 
-        self.cached_rune.insert(key, font.clone());
+        self.cache.insert_glyph(key, font)
+    }
+
+    fn format_glyph(&self, mut font: Font, fmt: u32) -> Font {
+        if fmt != 0 && !font.colored && font.width() > 0 {
+            if fmt & 0x100 != 0 { // FMT_Obfuscated
+                for pixel in font.mat.pixels_mut() {
+                    pixel[0] = 1;
+                    pixel[1] = 1;
+                    pixel[2] = 1;
+                }
+            }
+            if fmt & 0x200 != 0 { // FMT_Bold
+                let h = font.mat.height();
+                let w = font.mat.width();
+                let pad = self.to_px(BOLD_PAD).max(1) as u32;
+                let mut new_mat = RgbaImage::new(w + pad, h);
+                
+                // Blend the copies so a transparent column does not erase the smear
+                for off in 0..pad {
+                    blend_image(&mut new_mat, &font.mat, off as i64, 0);
+                }
+                font.mat = new_mat;
+            }
+        }
+        
         font
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct MemSource {
+        name: String,
+        files: HashMap<String, Vec<u8>>,
+    }
+
+    impl GlyphSource for MemSource {
+        fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+            self.files.get(file_name).cloned()
+        }
+
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn png(img: &RgbaImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+        png
+    }
+
+    fn mem_font(name: &str, images: Vec<(String, RgbaImage)>) -> RuneFont {
+        let files = images.into_iter().map(|(file_name, img)| (file_name, png(&img))).collect();
+        RuneFont::from_source(Box::new(MemSource { name: format!("<test {}>", name), files }))
+    }
+
+    // Glyph page at 32px cells with a `width` wide bar in the cell of UTF-16 unit `unit`
+    fn page_with(unit: u32, width: u32) -> (String, RgbaImage) {
+        let mut page = RgbaImage::new(512, 512);
+        let (x, y) = ((unit & 0xF) * 32, ((unit & 0xF0) >> 4) * 32);
+        for dy in 4..28 {
+            for dx in 0..width {
+                page.put_pixel(x + dx, y + dy, Rgba([255, 255, 255, 255]));
+            }
+        }
+        (page_file_name(unit >> 8), page)
+    }
+
+    #[test]
+    fn ascii_glyphs_keep_their_bottom_row() {
+        // `default8.png` with 8px cells where 'g' only has a pixel in its bottom row
+        let mut sheet = RgbaImage::new(128, 128);
+        let code = 'g' as u32;
+        sheet.put_pixel((code % 16) * 8, (code / 16) * 8 + 7, Rgba([255, 255, 255, 255]));
+        let font = mem_font("ascii", vec![(ASCII_SHEET.to_string(), sheet)]);
+        let glyph = font.get_font("g", 0);
+        assert_eq!((glyph.width(), glyph.mat.height()), (4, LOGICAL_CELL));
+        assert!((28..32).all(|y| glyph.mat.get_pixel(0, y)[3] > 0));
+
+        let image = crate::render::render_with_font(font, "g", None);
+        assert_eq!(image.height(), LOGICAL_CELL);
+        assert!(image.get_pixel(0, LOGICAL_CELL - 1)[3] > 0);
+    }
+
+    #[test]
+    fn surrogate_pairs_are_as_wide_as_both_halves() {
+        // U+1F600 is D83D DE00 in UTF-16
+        let mut font = mem_font("surrogates", vec![page_with(0xD83D, 8), page_with(0xDE00, 6)]);
+        font.set_astral_policy(AstralPolicy::Surrogates);
+        let bold = crate::render::FMT_Bold;
+        assert_eq!(font.get_font("😀", 0).width(), 8 + 6 + CHAR_HORIZON_PADDING as u32);
+        assert_eq!(font.get_font("😀", bold).width(), 8 + 6 + 2 * BOLD_PAD as u32 + CHAR_HORIZON_PADDING as u32);
+
+        for text in ["😀", "§l😀", "§l😀§r😀", "§o😀"] {
+            let mut simulator = crate::render::TellRawSimulator::new(font.clone(), Default::default());
+            let measured = simulator.measure(text)[0];
+            assert_eq!(measured as u32, simulator.render(text).width(), "{}", text);
+        }
+
+        // Without the pages the pair is one missing-glyph box
+        font.set_astral_policy(AstralPolicy::MissingGlyph);
+        assert_eq!(font.get_font("😀", 0).width(), MISSING_GLYPH_WIDTH as u32);
+    }
+
+    #[test]
+    fn invisible_characters_are_not_missing() {
+        let font = mem_font("invisible", vec![page_with(0x2010, 8)]);
+        for rune in ["\u{200B}", "\u{200D}", "\u{FE0F}", "\u{E0100}"] {
+            for fmt in [0, crate::render::FMT_Bold] {
+                let glyph = font.get_font(rune, fmt);
+                assert_eq!((glyph.width(), glyph.missing), (0, false), "{:?}", rune);
+            }
+        }
+        assert!(font.get_font("\u{2010}", 0).width() > 0);
+
+        let mut simulator = crate::render::TellRawSimulator::new(font, Default::default());
+        let output = simulator.render_with_report("a\u{200D}\u{FE0F}\u{2010}").unwrap();
+        assert_eq!(output.missing, vec!['a']);
+    }
+
+    #[test]
+    fn width_tables_do_not_fill_the_shared_cache() {
+        let font = mem_font("generate", vec![page_with(0x2010, 8)]);
+        let table = crate::WidthTable::generate(&font);
+        assert_eq!(table.width(0x2010), 8);
+        assert!(font.cache.page(0x20).is_none());
+        assert_eq!(font.get_font("\u{2010}", 0).width(), 8);
+    }
+
+    #[test]
+    fn preload_stops_at_the_page_budget() {
+        // Four 1 MiB pages into a cache that holds two and a half
+        let pages = (0..4).map(|page| page_with(page << 8, 4)).collect();
+        let mut font = mem_font("preload", pages);
+        font.cache = Arc::new(GlyphCache::with_budget(5 * 512 * 512 * 4 / 2, 1 << 20));
+        assert_eq!(font.preload(), 3);
+        assert!(font.cache.page(3).is_none());
+    }
+
+    // Rewrite `path` with a clearly later modification time than the first write
+    fn rewrite(path: &Path, bytes: &[u8]) {
+        let file = std::fs::File::create(path).unwrap();
+        std::io::Write::write_all(&mut &file, bytes).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+    }
+
+    #[test]
+    fn changed_pages_are_read_again() {
+        let dir = std::env::temp_dir().join(format!("mcbe-text-impact-{}-reload", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (file_name, page) = page_with('A' as u32, 8);
+        std::fs::write(dir.join(&file_name), png(&page)).unwrap();
+        let before = RuneFont::new(dir.to_str().unwrap());
+        assert_eq!(before.get_font("A", 0).width(), 8);
+
+        rewrite(&dir.join(&file_name), &png(&page_with('A' as u32, 12).1));
+        let after = RuneFont::new(dir.to_str().unwrap()).get_font("A", 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(after.width(), 12);
+    }
+
+    #[test]
+    fn changed_packs_are_read_again() {
+        let zip_with = |width: u32| {
+            let mut buf = std::io::Cursor::new(Vec::new());
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            zip.start_file("font/glyph_00.png", zip::write::FileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut zip, &png(&page_with('A' as u32, width).1)).unwrap();
+            zip.finish().unwrap();
+            drop(zip);
+            buf.into_inner()
+        };
+        let path = std::env::temp_dir().join(format!("mcbe-text-impact-{}-reload.mcpack", std::process::id()));
+        std::fs::write(&path, zip_with(8)).unwrap();
+        let before = RuneFont::from_pack(path.to_str().unwrap()).unwrap();
+        assert_eq!(before.get_font("A", 0).width(), 8);
+
+        rewrite(&path, &zip_with(12));
+        let after = RuneFont::from_pack(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(after.get_font("A", 0).width(), 12);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,437 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// Largest font file read from an archive; glyph pages of 4096px HD packs are well below it
+const MAX_ZIP_ENTRY: u64 = 64 * 1024 * 1024;
+
+// Archive path and modification time
+type ArchiveKey = (PathBuf, Option<SystemTime>);
+
+lazy_static::lazy_static! {
+    // Archives already read, so reopening a pack by path does not decompress it again
+    static ref OPENED: Mutex<HashMap<ArchiveKey, Arc<ZipSource>>> = Mutex::new(HashMap::new());
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+// Where font images (`glyph_XX.png`, ...) are read from
+
+pub trait GlyphSource: Send + Sync {
+    // Raw bytes of a file in the font directory, e.g. `glyph_00.png`
+    fn read(&self, file_name: &str) -> Option<Vec<u8>>;
+
+    // Human readable name used when reporting where a page came from
+    fn name(&self) -> String;
+
+    // Name of the source that supplies `file_name`, if any
+    fn origin(&self, file_name: &str) -> Option<String> {
+        self.read(file_name).map(|_| self.name())
+    }
+
+    // Changes whenever the files behind `name` change, so cached pages are not reused
+    fn revision(&self) -> String {
+        String::new()
+    }
+}
+
+fn modified_revision(modified: Option<SystemTime>) -> String {
+    modified
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos().to_string())
+        .unwrap_or_default()
+}
+
+impl<T: GlyphSource + ?Sized> GlyphSource for Arc<T> {
+    fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        (**self).read(file_name)
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn origin(&self, file_name: &str) -> Option<String> {
+        (**self).origin(file_name)
+    }
+
+    fn revision(&self) -> String {
+        (**self).revision()
+    }
+}
+
+pub fn page_file_name(page: u32) -> String {
+    format!("glyph_{:02X}.png", page)
+}
+
+// Loose files in a directory, e.g. `font_png/` or `<pack>/font/`
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(root: &Path) -> Self {
+        Self { root: root.to_path_buf() }
+    }
+}
+
+impl GlyphSource for DirSource {
+    fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.root.join(file_name)).ok()
+    }
+
+    fn name(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn origin(&self, file_name: &str) -> Option<String> {
+        self.root.join(file_name).is_file().then(|| self.name())
+    }
+
+    // Latest modification of the directory or any file in it
+    fn revision(&self) -> String {
+        let entries = std::fs::read_dir(&self.root).into_iter().flatten().flatten();
+        let latest = entries
+            .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+            .chain(std::fs::metadata(&self.root).and_then(|m| m.modified()).ok())
+            .max();
+        modified_revision(latest)
+    }
+}
+
+// `font/*` entries of a `.zip` / `.mcpack` resource pack, read into memory once.
+// The pack is either at the root of the archive or inside a single top level folder.
+pub struct ZipSource {
+    name: String,
+    modified: Option<SystemTime>,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl ZipSource {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let modified = file.metadata()?.modified().ok();
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        let names: Vec<String> = archive.file_names().map(|name| name.replace('\\', "/")).collect();
+        let font_dir = Self::font_dir(&names);
+
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut entry = archive
+                .by_index(i)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            if entry.is_dir() {
+                continue;
+            }
+            let entry_name = entry.name().replace('\\', "/");
+            let Some(file_name) = entry_name.strip_prefix(&font_dir) else {
+                continue;
+            };
+            if file_name.is_empty() || file_name.contains('/') {
+                continue;
+            }
+            if files.contains_key(file_name) {
+                return Err(invalid_data(format!("duplicate entry '{}' in {}", entry_name, path.display())));
+            }
+            if entry.size() > MAX_ZIP_ENTRY {
+                return Err(invalid_data(format!("entry '{}' in {} is too large", entry_name, path.display())));
+            }
+            // The declared size may lie, so never read past the limit either
+            let mut buf = Vec::with_capacity(entry.size() as usize);
+            (&mut entry).take(MAX_ZIP_ENTRY + 1).read_to_end(&mut buf)?;
+            if buf.len() as u64 > MAX_ZIP_ENTRY {
+                return Err(invalid_data(format!("entry '{}' in {} is too large", entry_name, path.display())));
+            }
+            files.insert(file_name.to_string(), buf);
+        }
+
+        Ok(Self {
+            name: path.display().to_string(),
+            modified,
+            files,
+        })
+    }
+
+    // Like `open`, but an archive is only read again after it changed on disk
+    pub fn open_shared(path: &Path) -> std::io::Result<Arc<Self>> {
+        let key = (path.canonicalize()?, std::fs::metadata(path)?.modified().ok());
+        if let Some(source) = OPENED.lock().unwrap().get(&key) {
+            return Ok(source.clone());
+        }
+        let source = Arc::new(Self::open(path)?);
+        let mut opened = OPENED.lock().unwrap();
+        opened.retain(|(path, _), _| *path != key.0);
+        opened.insert(key, source.clone());
+        Ok(source)
+    }
+
+    // `font/` at the root of the archive, or in the folder the whole pack was zipped in
+    fn font_dir(names: &[String]) -> String {
+        if names.iter().any(|name| name.starts_with("font/")) {
+            return "font/".to_string();
+        }
+        let mut top = names.iter().filter_map(|name| name.split_once('/').map(|(dir, _)| dir));
+        match top.next() {
+            Some(first) if top.all(|dir| dir == first) && names.iter().all(|name| name.contains('/')) => {
+                format!("{}/font/", first)
+            }
+            _ => "font/".to_string(),
+        }
+    }
+}
+
+impl GlyphSource for ZipSource {
+    fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        self.files.get(file_name).cloned()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn origin(&self, file_name: &str) -> Option<String> {
+        self.files.contains_key(file_name).then(|| self.name())
+    }
+
+    fn revision(&self) -> String {
+        modified_revision(self.modified)
+    }
+}
+
+#[cfg(feature = "embed-font")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_font.rs"));
+}
+
+// The vanilla `font_png/` pages compiled into the crate
+#[cfg(feature = "embed-font")]
+pub struct EmbeddedSource;
+
+#[cfg(feature = "embed-font")]
+impl GlyphSource for EmbeddedSource {
+    fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        self.bytes(file_name).map(|bytes| bytes.to_vec())
+    }
+
+    fn name(&self) -> String {
+        "<embedded>".to_string()
+    }
+
+    fn origin(&self, file_name: &str) -> Option<String> {
+        self.bytes(file_name).map(|_| self.name())
+    }
+}
+
+#[cfg(feature = "embed-font")]
+impl EmbeddedSource {
+    fn bytes(&self, file_name: &str) -> Option<&'static [u8]> {
+        embedded::EMBEDDED_FONT
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .map(|(_, bytes)| *bytes)
+    }
+}
+
+// Open a font location: a `.zip` / `.mcpack` archive, an unpacked pack root with
+// `manifest.json`, or a plain directory of glyph pages
+pub fn open_source(path: &Path) -> std::io::Result<Box<dyn GlyphSource>> {
+    if path.is_file() {
+        return Ok(Box::new(ZipSource::open_shared(path)?));
+    }
+    if path.join("manifest.json").is_file() {
+        return Ok(Box::new(DirSource::new(&path.join("font"))));
+    }
+    if path.is_dir() {
+        return Ok(Box::new(DirSource::new(path)));
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("font source '{}' not found", path.display()),
+    ))
+}
+
+// Resource packs stacked like the game's pack list: earlier layers take precedence
+// per file, later layers (usually the vanilla font) fill in the rest
+#[derive(Default)]
+pub struct FontStack {
+    layers: Vec<Box<dyn GlyphSource>>,
+}
+
+impl FontStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Add a layer below the existing ones
+    pub fn push(&mut self, source: Box<dyn GlyphSource>) -> &mut Self {
+        self.layers.push(source);
+        self
+    }
+
+    // `paths` ordered from highest to lowest priority
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> std::io::Result<Self> {
+        let mut stack = Self::new();
+        for path in paths {
+            stack.push(open_source(path.as_ref())?);
+        }
+        Ok(stack)
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    // Which layer supplies each glyph page that exists in the stack
+    pub fn page_origins(&self) -> BTreeMap<u32, String> {
+        (0..=0xFF)
+            .filter_map(|page| self.origin(&page_file_name(page)).map(|name| (page, name)))
+            .collect()
+    }
+}
+
+impl GlyphSource for FontStack {
+    fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+        self.layers.iter().find_map(|layer| layer.read(file_name))
+    }
+
+    fn name(&self) -> String {
+        self.layers.iter().map(|l| l.name()).collect::<Vec<_>>().join(" > ")
+    }
+
+    fn origin(&self, file_name: &str) -> Option<String> {
+        self.layers.iter().find_map(|layer| layer.origin(file_name))
+    }
+
+    fn revision(&self) -> String {
+        self.layers.iter().map(|l| l.revision()).collect::<Vec<_>>().join(" > ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(name: &str, entries: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mcbe-text-impact-{}-{}.zip", std::process::id(), name));
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for entry in entries {
+            zip.start_file(*entry, options).unwrap();
+            zip.write_all(entry.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    fn files(name: &str, entries: &[&str]) -> Vec<(String, String)> {
+        let path = write_zip(name, entries);
+        let source = ZipSource::open(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let mut files: Vec<_> = source
+            .files
+            .into_iter()
+            .map(|(name, bytes)| (name, String::from_utf8(bytes).unwrap()))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn reads_font_at_the_root() {
+        assert_eq!(
+            files("root", &["manifest.json", "font/glyph_00.png", "textures/font/glyph_01.png", "font/x/glyph_02.png"]),
+            vec![("glyph_00.png".to_string(), "font/glyph_00.png".to_string())]
+        );
+    }
+
+    #[test]
+    fn reads_font_in_a_single_top_level_folder() {
+        assert_eq!(
+            files("folder", &["pack/manifest.json", "pack/font/glyph_00.png", "pack/textures/font/glyph_01.png"]),
+            vec![("glyph_00.png".to_string(), "pack/font/glyph_00.png".to_string())]
+        );
+        assert!(files("two", &["a/font/glyph_00.png", "b/font/glyph_01.png"]).is_empty());
+    }
+
+    #[test]
+    fn rejects_duplicate_entries() {
+        let path = write_zip("duplicate", &["font/glyph_00.png", "font\\glyph_00.png"]);
+        let result = ZipSource::open(&path);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(result.err().map(|e| e.kind()), Some(std::io::ErrorKind::InvalidData));
+    }
+
+    // In-memory layer holding `files`, each file's content being its layer and name
+    struct Layer(&'static str, &'static [&'static str]);
+
+    impl GlyphSource for Layer {
+        fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+            self.1.contains(&file_name).then(|| format!("{}:{}", self.0, file_name).into_bytes())
+        }
+
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    fn stack() -> FontStack {
+        let mut stack = FontStack::new();
+        stack
+            .push(Box::new(Layer("pack", &["glyph_00.png", "glyph_4E.png"])))
+            .push(Box::new(Layer("vanilla", &["glyph_00.png", "glyph_01.png"])));
+        stack
+    }
+
+    #[test]
+    fn first_layer_with_a_page_wins() {
+        let stack = stack();
+        assert_eq!(stack.read("glyph_00.png"), Some(b"pack:glyph_00.png".to_vec()));
+        assert_eq!(stack.origin("glyph_00.png"), Some("pack".to_string()));
+        assert_eq!(stack.name(), "pack > vanilla");
+    }
+
+    #[test]
+    fn missing_pages_fall_through_to_later_layers() {
+        let stack = stack();
+        assert_eq!(stack.read("glyph_01.png"), Some(b"vanilla:glyph_01.png".to_vec()));
+        assert_eq!(stack.read("glyph_02.png"), None);
+        let origins: Vec<(u32, String)> = stack.page_origins().into_iter().collect();
+        assert_eq!(
+            origins,
+            vec![(0x00, "pack".to_string()), (0x01, "vanilla".to_string()), (0x4E, "pack".to_string())]
+        );
+    }
+
+    #[cfg(feature = "embed-font")]
+    #[test]
+    fn embedded_pages_match_font_png() {
+        let on_disk = DirSource::new(Path::new("font_png"));
+        assert_eq!(EmbeddedSource.read("glyph_00.png"), on_disk.read("glyph_00.png"));
+        assert_eq!(EmbeddedSource.origin("glyph_4E.png"), Some("<embedded>".to_string()));
+        assert_eq!(EmbeddedSource.read("glyph_E5.png"), None);
+
+        let font = crate::font::RuneFont::embedded();
+        let glyph = font.get_font("中", 0);
+        assert!(!glyph.missing && glyph.width() > 0);
+    }
+
+    #[test]
+    fn shared_archives_are_read_once() {
+        let path = write_zip("shared", &["font/glyph_00.png"]);
+        let first = ZipSource::open_shared(&path).unwrap();
+        let second = ZipSource::open_shared(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
@@ -0,0 +1,128 @@
+// `§` format codes read the way `TellRawSimulator` reads them: `§§` is a literal `§`,
+// unknown codes print their character, and a `§` at the end of a line is dropped.
+// Shared by everything that measures or splits formatted text.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub color: Option<char>,
+    pub bold: bool,
+    pub italic: bool,
+    pub obfuscated: bool,
+}
+
+impl Style {
+    // Apply `§<code>`; false if the code is unknown and is printed instead
+    pub fn apply(&mut self, code: char) -> bool {
+        match code {
+            'r' => *self = Self::default(),
+            'l' => self.bold = true,
+            'o' => self.italic = true,
+            'k' => self.obfuscated = true,
+            '0'..='9' | 'a'..='v' => self.color = Some(code),
+            _ => return false,
+        }
+        true
+    }
+
+    // Format codes that set up this style from the default one
+    pub fn codes(&self) -> String {
+        let flags = [(self.obfuscated, 'k'), (self.bold, 'l'), (self.italic, 'o')];
+        self.color
+            .into_iter()
+            .chain(flags.iter().filter(|(on, _)| *on).map(|(_, code)| *code))
+            .map(|code| format!("§{}", code))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    // One character, `§§` or a format code such as `§l`
+    pub source: &'a str,
+    // Printed character, `None` for a format code
+    pub ch: Option<char>,
+    // Style in effect after this token, the one `ch` is drawn in
+    pub style: Style,
+}
+
+pub struct FormatScanner<'a> {
+    rest: &'a str,
+    style: Style,
+}
+
+impl<'a> FormatScanner<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self::with_style(text, Style::default())
+    }
+
+    // Continue with the style a previous line ended in
+    pub fn with_style(text: &'a str, style: Style) -> Self {
+        Self { rest: text, style }
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+}
+
+impl<'a> Iterator for FormatScanner<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let mut chars = self.rest.chars();
+        let first = chars.next()?;
+        let (len, ch) = match (first, chars.next()) {
+            ('§', None) => {
+                self.rest = "";
+                return None;
+            }
+            ('§', Some('\n')) => (first.len_utf8(), None),
+            ('§', Some('§')) => (2 * first.len_utf8(), Some('§')),
+            ('§', Some(code)) => {
+                let known = self.style.apply(code);
+                (first.len_utf8() + code.len_utf8(), (!known).then_some(code))
+            }
+            _ => (first.len_utf8(), Some(first)),
+        };
+        let (source, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(Token { source, ch, style: self.style })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printed(text: &str) -> String {
+        FormatScanner::new(text).filter_map(|t| t.ch).collect()
+    }
+
+    #[test]
+    fn codes_are_not_printed() {
+        assert_eq!(printed("§a§lhi§r!"), "hi!");
+        assert_eq!(printed("a§§b"), "a§b");
+        assert_eq!(printed("§zx§"), "zx");
+    }
+
+    #[test]
+    fn tokens_keep_their_source_and_style() {
+        let tokens: Vec<Token> = FormatScanner::new("§lA§§§r").collect();
+        let sources: Vec<&str> = tokens.iter().map(|t| t.source).collect();
+        assert_eq!(sources, vec!["§l", "A", "§§", "§r"]);
+        assert!(tokens[1].style.bold && tokens[2].style.bold && !tokens[3].style.bold);
+    }
+
+    #[test]
+    fn styles_round_trip_through_codes() {
+        let mut scanner = FormatScanner::new("§o§c§kx§l");
+        scanner.by_ref().for_each(drop);
+        let style = scanner.style();
+        let codes = style.codes();
+        assert_eq!(codes, "§c§k§l§o");
+        let mut again = FormatScanner::new(&codes);
+        again.by_ref().for_each(drop);
+        assert_eq!(again.style(), style);
+        assert_eq!(printed(&format!("{}x", style.codes())), "x");
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use image::RgbaImage;
+use crate::font::{AstralPolicy, Font, MissingGlyphPolicy};
+
+// Process-wide cache of decoded glyph pages and finished glyphs. Every `RuneFont` reading
+// the same source shares one `GlyphCache`, so pages are decoded once per process instead of
+// once per render, and entries are handed out behind `Arc` instead of being cloned.
+
+static PAGE_BUDGET: AtomicUsize = AtomicUsize::new(256 * 1024 * 1024);
+static GLYPH_BUDGET: AtomicUsize = AtomicUsize::new(64 * 1024 * 1024);
+
+lazy_static::lazy_static! {
+    // Source name to the revision its cache was filled from
+    static ref CACHES: Mutex<HashMap<String, (String, Arc<GlyphCache>)>> = Mutex::new(HashMap::new());
+}
+
+// Memory limits in bytes for caches created afterwards
+pub fn set_budget(pages: usize, glyphs: usize) {
+    PAGE_BUDGET.store(pages, Ordering::Relaxed);
+    GLYPH_BUDGET.store(glyphs, Ordering::Relaxed);
+}
+
+pub(crate) fn page_budget() -> usize {
+    PAGE_BUDGET.load(Ordering::Relaxed)
+}
+
+// Drop every cache, e.g. after the font files changed on disk
+pub fn clear_all() {
+    CACHES.lock().unwrap().clear();
+}
+
+pub struct Page {
+    pub image: RgbaImage,
+    pub colored: bool,
+}
+
+// Everything a finished glyph depends on besides the source
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub rune: String,
+    pub fmt: u32,
+    pub cell_size: u32,
+    pub ascii_sheet: bool,
+    pub astral_policy: AstralPolicy,
+    pub missing_policy: MissingGlyphPolicy,
+}
+
+// Map bounded by the total size of its values; the least recently used entries are
+// evicted first. Every use takes a new generation, and `order` maps generations back to
+// keys so the oldest entry is always the first one.
+struct Bounded<K, V> {
+    entries: HashMap<K, (Arc<V>, usize, u64)>,
+    order: BTreeMap<u64, K>,
+    bytes: usize,
+    budget: usize,
+    generation: u64,
+    evictions: usize,
+}
+
+impl<K: Eq + Hash + Clone, V> Bounded<K, V> {
+    fn new(budget: usize) -> Self {
+        Self { entries: HashMap::new(), order: BTreeMap::new(), bytes: 0, budget, generation: 0, evictions: 0 }
+    }
+
+    fn get(&mut self, key: &K) -> Option<Arc<V>> {
+        let (value, _, used) = self.entries.get_mut(key)?;
+        self.generation += 1;
+        self.order.remove(used);
+        self.order.insert(self.generation, key.clone());
+        *used = self.generation;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: Arc<V>, size: usize) {
+        self.generation += 1;
+        self.order.insert(self.generation, key.clone());
+        if let Some((_, old, used)) = self.entries.insert(key, (value, size, self.generation)) {
+            self.bytes -= old;
+            self.order.remove(&used);
+        }
+        self.bytes += size;
+
+        if self.bytes > self.budget {
+            // Evict down to 3/4 of the budget so eviction does not run on every insert
+            let target = self.budget / 4 * 3;
+            while self.bytes > target && self.entries.len() > 1 {
+                let Some((_, oldest)) = self.order.pop_first() else { break };
+                if let Some((_, size, _)) = self.entries.remove(&oldest) {
+                    self.bytes -= size;
+                    self.evictions += 1;
+                }
+            }
+        }
+    }
+}
+
+// `default8.png` of a source, looked up once
+enum AsciiSheet {
+    Absent,
+    Loaded(Arc<RgbaImage>),
+}
+
+fn image_bytes(img: &RgbaImage) -> usize {
+    img.as_raw().len()
+}
+
+pub struct GlyphCache {
+    pages: Mutex<Bounded<u32, Page>>,
+    glyphs: Mutex<Bounded<GlyphKey, Font>>,
+    ascii_sheet: OnceLock<AsciiSheet>,
+}
+
+impl GlyphCache {
+    fn new() -> Self {
+        Self::with_budget(page_budget(), GLYPH_BUDGET.load(Ordering::Relaxed))
+    }
+
+    // A cache of its own, not shared through `for_source`
+    pub(crate) fn with_budget(pages: usize, glyphs: usize) -> Self {
+        Self {
+            pages: Mutex::new(Bounded::new(pages)),
+            glyphs: Mutex::new(Bounded::new(glyphs)),
+            ascii_sheet: OnceLock::new(),
+        }
+    }
+
+    // The shared cache for the source called `name`; a new `revision` of the source
+    // replaces the cache filled from the old one
+    pub fn for_source(name: &str, revision: &str) -> Arc<Self> {
+        let mut caches = CACHES.lock().unwrap();
+        match caches.get(name) {
+            Some((cached, cache)) if cached == revision => cache.clone(),
+            _ => {
+                let cache = Arc::new(Self::new());
+                caches.insert(name.to_string(), (revision.to_string(), cache.clone()));
+                cache
+            }
+        }
+    }
+
+    pub fn page(&self, page: u32) -> Option<Arc<Page>> {
+        self.pages.lock().unwrap().get(&page)
+    }
+
+    // Pages are decoded outside the lock, so two workers may race to insert the same page
+    pub fn insert_page(&self, page: u32, value: Page) -> Arc<Page> {
+        let size = image_bytes(&value.image);
+        let value = Arc::new(value);
+        self.pages.lock().unwrap().insert(page, value.clone(), size);
+        value
+    }
+
+    // Pages dropped so far to stay within the budget
+    pub fn page_evictions(&self) -> usize {
+        self.pages.lock().unwrap().evictions
+    }
+
+    pub fn glyph(&self, key: &GlyphKey) -> Option<Arc<Font>> {
+        self.glyphs.lock().unwrap().get(key)
+    }
+
+    pub fn insert_glyph(&self, key: GlyphKey, font: Font) -> Arc<Font> {
+        let size = image_bytes(&font.mat) + key.rune.len();
+        let font = Arc::new(font);
+        self.glyphs.lock().unwrap().insert(key, font.clone(), size);
+        font
+    }
+
+    pub fn ascii_sheet(&self, load: impl FnOnce() -> Option<RgbaImage>) -> Option<Arc<RgbaImage>> {
+        let sheet = self.ascii_sheet.get_or_init(|| match load() {
+            Some(img) => AsciiSheet::Loaded(Arc::new(img)),
+            None => AsciiSheet::Absent,
+        });
+        match sheet {
+            AsciiSheet::Loaded(img) => Some(img.clone()),
+            AsciiSheet::Absent => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(cache: &Bounded<u32, ()>) -> Vec<u32> {
+        let mut keys: Vec<u32> = cache.entries.keys().copied().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn evicts_least_recently_used_down_to_three_quarters() {
+        let mut cache = Bounded::new(40);
+        for key in 0..4 {
+            cache.insert(key, Arc::new(()), 10);
+        }
+        assert!(cache.get(&0).is_some());
+        // 50 bytes, so the oldest entries go until 30 are left
+        cache.insert(4, Arc::new(()), 10);
+        assert_eq!(keys(&cache), vec![0, 3, 4]);
+        assert_eq!((cache.bytes, cache.evictions), (30, 2));
+        assert_eq!(cache.order.len(), cache.entries.len());
+    }
+
+    #[test]
+    fn replacing_an_entry_updates_its_size_and_age() {
+        let mut cache = Bounded::new(40);
+        cache.insert(0, Arc::new(()), 5);
+        cache.insert(1, Arc::new(()), 10);
+        cache.insert(2, Arc::new(()), 10);
+        cache.insert(0, Arc::new(()), 10);
+        assert_eq!((cache.bytes, cache.order.len()), (30, 3));
+        cache.insert(3, Arc::new(()), 11);
+        assert_eq!(keys(&cache), vec![0, 3]);
+    }
+
+    #[test]
+    fn keeps_a_single_entry_over_budget() {
+        let mut cache = Bounded::new(10);
+        cache.insert(0, Arc::new(()), 100);
+        assert_eq!((keys(&cache), cache.evictions), (vec![0], 0));
+    }
+}
@@ -0,0 +1,333 @@
+use serde::Serialize;
+use serde_json::Value;
+use crate::cmd_helper::TellrawContext;
+use crate::format_code::Style;
+use crate::lang::LangFile;
+use crate::rawtext::{Component, RawText, TranslateWith};
+
+// Import Java Edition JSON text components as MCBE rawtext
+
+const JAVA_COLORS: [(&str, char, (u8, u8, u8)); 16] = [
+    ("black", '0', (0, 0, 0)),
+    ("dark_blue", '1', (0, 0, 170)),
+    ("dark_green", '2', (0, 170, 0)),
+    ("dark_aqua", '3', (0, 170, 170)),
+    ("dark_red", '4', (170, 0, 0)),
+    ("dark_purple", '5', (170, 0, 170)),
+    ("gold", '6', (255, 170, 0)),
+    ("gray", '7', (170, 170, 170)),
+    ("dark_gray", '8', (85, 85, 85)),
+    ("blue", '9', (85, 85, 255)),
+    ("green", 'a', (85, 255, 85)),
+    ("aqua", 'b', (85, 255, 255)),
+    ("red", 'c', (255, 85, 85)),
+    ("light_purple", 'd', (255, 85, 255)),
+    ("yellow", 'e', (255, 255, 85)),
+    ("white", 'f', (255, 255, 255)),
+];
+
+// Keys that change nothing visible in Bedrock chat
+const UNSUPPORTED_KEYS: [&str; 5] = ["clickEvent", "hoverEvent", "insertion", "font", "nbt"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JavaWarning {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JavaImport {
+    pub rawtext: RawText,
+    pub warnings: Vec<JavaWarning>,
+}
+
+// Codes switching from `from` to `to`; Bedrock colors keep bold/italic, so dropping a
+// flag or the color needs a `§r` first
+fn transition(from: &Style, to: &Style) -> String {
+    let needs_reset = (from.bold && !to.bold)
+        || (from.italic && !to.italic)
+        || (from.obfuscated && !to.obfuscated)
+        || (from.color.is_some() && to.color.is_none());
+    if needs_reset {
+        return format!("§r{}", to.codes());
+    }
+    let color = to.color.filter(|_| to.color != from.color);
+    let flags = [
+        (from.obfuscated, to.obfuscated, 'k'),
+        (from.bold, to.bold, 'l'),
+        (from.italic, to.italic, 'o'),
+    ];
+    color
+        .into_iter()
+        .chain(flags.iter().filter(|(was, now, _)| *now && !*was).map(|(_, _, code)| *code))
+        .map(|code| format!("§{}", code))
+        .collect()
+}
+
+// Nearest legacy color for `#RRGGBB`
+fn nearest_color(hex: &str) -> Option<char> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let v = u32::from_str_radix(hex, 16).ok()?;
+    let (r, g, b) = ((v >> 16) as i32 & 0xFF, (v >> 8) as i32 & 0xFF, v as i32 & 0xFF);
+    JAVA_COLORS
+        .iter()
+        .min_by_key(|(_, _, (cr, cg, cb))| {
+            let (dr, dg, db) = (r - *cr as i32, g - *cg as i32, b - *cb as i32);
+            dr * dr + dg * dg + db * db
+        })
+        .map(|(_, code, _)| *code)
+}
+
+struct Importer {
+    out: Vec<Component>,
+    warnings: Vec<JavaWarning>,
+    // Style in effect at the end of `out`; `None` when unknown after a translate
+    current: Option<Style>,
+}
+
+impl Importer {
+    fn warn(&mut self, path: &str, message: String) {
+        self.warnings.push(JavaWarning {
+            path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+            message,
+        });
+    }
+
+    fn apply_style(&mut self, style: &Style) -> String {
+        let codes = match &self.current {
+            Some(current) => transition(current, style),
+            None => format!("§r{}", style.codes()),
+        };
+        self.current = Some(*style);
+        codes
+    }
+
+    fn push_text(&mut self, style: &Style, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let text = format!("{}{}", self.apply_style(style), text);
+        if let Some(Component::Text { text: last }) = self.out.last_mut() {
+            last.push_str(&text);
+        } else {
+            self.out.push(Component::text(&text));
+        }
+    }
+
+    fn push_component(&mut self, style: &Style, component: Component) {
+        let codes = self.apply_style(style);
+        if !codes.is_empty() {
+            if let Some(Component::Text { text: last }) = self.out.last_mut() {
+                last.push_str(&codes);
+            } else {
+                self.out.push(Component::text(&codes));
+            }
+        }
+        self.out.push(component);
+    }
+
+    // Style of `obj` inheriting from `parent`; warnings are only reported when `path` is given
+    fn read_style(&mut self, obj: &serde_json::Map<String, Value>, parent: &Style, path: Option<&str>) -> Style {
+        let mut style = *parent;
+        if let Some(color) = obj.get("color").and_then(|v| v.as_str()) {
+            if let Some((_, code, _)) = JAVA_COLORS.iter().find(|(name, _, _)| *name == color) {
+                style.color = Some(*code);
+            } else if color == "reset" {
+                style.color = None;
+            } else if let Some(code) = nearest_color(color) {
+                if let Some(path) = path {
+                    self.warn(path, format!("hex color '{}' approximated with §{}", color, code));
+                }
+                style.color = Some(code);
+            } else if let Some(path) = path {
+                self.warn(path, format!("unknown color '{}' ignored", color));
+            }
+        }
+        for (key, flag) in [("bold", &mut style.bold), ("italic", &mut style.italic), ("obfuscated", &mut style.obfuscated)] {
+            if let Some(v) = obj.get(key).and_then(|v| v.as_bool()) {
+                *flag = v;
+            }
+        }
+        let Some(path) = path else { return style };
+        for key in ["underlined", "strikethrough"] {
+            if obj.get(key).and_then(|v| v.as_bool()) == Some(true) {
+                self.warn(path, format!("'{}' is not supported by Bedrock and was dropped", key));
+            }
+        }
+        for key in UNSUPPORTED_KEYS {
+            if obj.contains_key(key) {
+                self.warn(path, format!("'{}' is not supported by Bedrock and was dropped", key));
+            }
+        }
+        style
+    }
+
+    // Translate arguments are imported on their own and become one rawtext element each.
+    // `state` is the style in effect where the argument is inserted (assuming in-order
+    // placeholders) and is updated to the style the argument leaves behind.
+    fn import_arg(&mut self, value: &Value, style: &Style, state: &mut Option<Style>, path: &str) -> Component {
+        let mut sub = Importer { out: Vec::new(), warnings: Vec::new(), current: *state };
+        sub.import(value, style, path);
+        self.warnings.append(&mut sub.warnings);
+        *state = sub.current;
+        match sub.out.len() {
+            1 => sub.out.pop().unwrap(),
+            _ => Component::RawText { rawtext: sub.out },
+        }
+    }
+
+    fn import(&mut self, value: &Value, parent: &Style, path: &str) {
+        match value {
+            Value::String(s) => self.push_text(parent, s),
+            Value::Number(_) | Value::Bool(_) => self.push_text(parent, &value.to_string()),
+            Value::Array(arr) => {
+                // The first element is the parent of the following ones
+                let Some(first) = arr.first() else { return };
+                let style = match first {
+                    Value::Object(obj) => self.read_style(obj, parent, None),
+                    _ => *parent,
+                };
+                self.import(first, parent, &format!("{}/0", path));
+                for (i, v) in arr.iter().enumerate().skip(1) {
+                    self.import(v, &style, &format!("{}/{}", path, i));
+                }
+            }
+            Value::Object(obj) => {
+                let style = self.read_style(obj, parent, Some(path));
+                if let Some(text) = obj.get("text") {
+                    match text {
+                        Value::String(s) => self.push_text(&style, s),
+                        other => self.push_text(&style, &other.to_string()),
+                    }
+                } else if let Some(key) = obj.get("translate").and_then(|v| v.as_str()) {
+                    let with = obj.get("with").and_then(|v| v.as_array()).map(|args| {
+                        let mut state = Some(style);
+                        let args = args
+                            .iter()
+                            .enumerate()
+                            .map(|(i, a)| self.import_arg(a, &style, &mut state, &format!("{}/with/{}", path, i)))
+                            .collect();
+                        TranslateWith::RawText(RawText::new(args))
+                    });
+                    let has_args = with.is_some();
+                    self.push_component(&style, Component::translate(key, with));
+                    if has_args {
+                        // Argument formatting leaks into the rest of the template
+                        self.current = None;
+                    }
+                } else if let Some(score) = obj.get("score").and_then(|v| v.as_object()) {
+                    let name = score.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    let objective = score.get("objective").and_then(|v| v.as_str()).unwrap_or("");
+                    self.push_component(&style, Component::score(name, objective));
+                } else if let Some(selector) = obj.get("selector").and_then(|v| v.as_str()) {
+                    if obj.contains_key("separator") {
+                        self.warn(path, "'separator' is not supported by Bedrock and was dropped".to_string());
+                    }
+                    self.push_component(&style, Component::selector(selector));
+                } else if let Some(keybind) = obj.get("keybind").and_then(|v| v.as_str()) {
+                    self.warn(path, format!("keybind '{}' shown as its key name", keybind));
+                    self.push_text(&style, keybind);
+                } else if !obj.contains_key("extra") {
+                    self.warn(path, "component has no content".to_string());
+                }
+
+                if let Some(extra) = obj.get("extra").and_then(|v| v.as_array()) {
+                    for (i, v) in extra.iter().enumerate() {
+                        self.import(v, &style, &format!("{}/extra/{}", path, i));
+                    }
+                }
+            }
+            Value::Null => self.warn(path, "null component ignored".to_string()),
+        }
+    }
+}
+
+pub fn java_to_rawtext(value: &Value) -> JavaImport {
+    let mut importer = Importer { out: Vec::new(), warnings: Vec::new(), current: Some(Style::default()) };
+    importer.import(value, &Style::default(), "");
+    JavaImport {
+        rawtext: RawText::new(importer.out),
+        warnings: importer.warnings,
+    }
+}
+
+// Java component straight to a § formatted string, translating with `lang`
+pub fn java_to_formatted(value: &Value, lang: &LangFile) -> (String, Vec<JavaWarning>) {
+    let import = java_to_rawtext(value);
+    let ctx = TellrawContext {
+        lang: lang.clone(),
+        ..Default::default()
+    };
+    (ctx.resolve(&import.rawtext), import.warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn formatted(value: Value) -> String {
+        java_to_formatted(&value, &LangFile::new()).0
+    }
+
+    fn warnings(value: Value) -> Vec<(String, String)> {
+        java_to_rawtext(&value).warnings.into_iter().map(|w| (w.path, w.message)).collect()
+    }
+
+    #[test]
+    fn extra_inherits_and_resets_style() {
+        let value = json!({
+            "text": "a", "color": "red", "bold": true,
+            "extra": ["b", {"text": "c", "bold": false}, {"text": "d", "color": "reset"}]
+        });
+        assert_eq!(formatted(value), "§c§lab§r§cc§r§ld");
+        // The first array element is the parent of the rest
+        assert_eq!(formatted(json!([{"text": "a", "italic": true}, "b", {"text": "c", "color": "gold"}])), "§oab§6c");
+    }
+
+    #[test]
+    fn translate_arguments_keep_their_own_style() {
+        let mut lang = LangFile::new();
+        lang.insert("greet", "Hi %s and %s!");
+        let value = json!({
+            "translate": "greet", "color": "green",
+            "with": [{"text": "A", "color": "gold"}, "B"],
+            "extra": ["x"]
+        });
+        let (text, warnings) = java_to_formatted(&value, &lang);
+        assert_eq!(text, "§aHi §6A and §aB!§r§ax");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn hex_colors_use_the_nearest_code() {
+        assert_eq!(formatted(json!({"text": "x", "color": "#FE5050"})), "§cx");
+        assert_eq!(formatted(json!({"text": "x", "color": "#101010"})), "§0x");
+        assert_eq!(
+            warnings(json!({"text": "x", "color": "#FE5050"})),
+            vec![("/".to_string(), "hex color '#FE5050' approximated with §c".to_string())]
+        );
+        assert_eq!(formatted(json!({"text": "x", "color": "#nothex"})), "x");
+    }
+
+    #[test]
+    fn dropped_features_are_reported() {
+        let value = json!({
+            "text": "x", "underlined": true, "clickEvent": {"action": "run_command", "value": "/say hi"},
+            "extra": [{"text": "y", "strikethrough": true}]
+        });
+        let dropped = |key: &str| format!("'{}' is not supported by Bedrock and was dropped", key);
+        assert_eq!(
+            warnings(value.clone()),
+            vec![
+                ("/".to_string(), dropped("underlined")),
+                ("/".to_string(), dropped("clickEvent")),
+                ("/extra/0".to_string(), dropped("strikethrough")),
+            ]
+        );
+        assert_eq!(formatted(value), "xy");
+    }
+}
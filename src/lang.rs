@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+// Bedrock `.lang` file: one `key=value` entry per line, `##` starts a comment
+#[derive(Debug, Clone, Default)]
+pub struct LangFile {
+    entries: HashMap<String, String>,
+}
+
+impl LangFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &str) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(Path::new(path))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut entries = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim_start_matches('\u{feff}');
+            if line.trim_start().starts_with("##") {
+                continue;
+            }
+            // Trailing `##` comments are separated from the value by a tab; other tabs are text
+            let line = line.find("\t##").map_or(line, |end| &line[..end]);
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                if !key.is_empty() {
+                    entries.insert(key.to_string(), value.trim_end().to_string());
+                }
+            }
+        }
+        Self { entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|s| s.as_str())
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.entries.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Resolve a key and fill its placeholders; unknown keys are shown as-is like the game does
+    pub fn translate(&self, key: &str, args: &[String]) -> String {
+        let template = self.get(key).unwrap_or(key);
+        format_template(template, args)
+    }
+}
+
+// Fill `%s`, `%d`, `%1`..`%9`, `%1$s` and `%%` placeholders
+pub fn format_template(template: &str, args: &[String]) -> String {
+    let chars: Vec<char> = template.chars().collect();
+    let mut out = String::new();
+    let mut next_arg = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch != '%' || i + 1 >= chars.len() {
+            out.push(ch);
+            i += 1;
+            continue;
+        }
+
+        let next = chars[i + 1];
+        match next {
+            '%' => {
+                out.push('%');
+                i += 2;
+            }
+            's' | 'd' => {
+                if let Some(arg) = args.get(next_arg) {
+                    out.push_str(arg);
+                }
+                next_arg += 1;
+                i += 2;
+            }
+            '1'..='9' => {
+                let idx = next.to_digit(10).unwrap() as usize - 1;
+                if let Some(arg) = args.get(idx) {
+                    out.push_str(arg);
+                }
+                i += 2;
+                // Optional `$s` / `$d` suffix
+                if i + 1 < chars.len() && chars[i] == '$' && (chars[i + 1] == 's' || chars[i + 1] == 'd') {
+                    i += 2;
+                }
+            }
+            _ => {
+                out.push(ch);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn sequential_placeholders() {
+        assert_eq!(format_template("%s has %d apples", &args(&["Steve", "3"])), "Steve has 3 apples");
+    }
+
+    #[test]
+    fn positional_placeholders() {
+        assert_eq!(format_template("%2 before %1", &args(&["a", "b"])), "b before a");
+        assert_eq!(format_template("%2$s, %1$d", &args(&["a", "b"])), "b, a");
+    }
+
+    #[test]
+    fn escapes_and_missing_arguments() {
+        assert_eq!(format_template("100%% of %s", &args(&[])), "100% of ");
+        assert_eq!(format_template("trailing %", &args(&[])), "trailing %");
+        assert_eq!(format_template("%x stays", &args(&["a"])), "%x stays");
+    }
+
+    #[test]
+    fn parse_skips_comments_and_trailing_tab_comments() {
+        let lang = LangFile::parse("\u{feff}## header\nitem.apple=Apple\t## note\nbad line\n=no key\n");
+        assert_eq!(lang.len(), 1);
+        assert_eq!(lang.translate("item.apple", &[]), "Apple");
+        assert_eq!(lang.translate("unknown.key", &[]), "unknown.key");
+    }
+
+    #[test]
+    fn parse_keeps_tabs_inside_values() {
+        let lang = LangFile::parse("a=x\ty
+b=x\ty\t## note
+c=x\t#y
+");
+        assert_eq!(lang.get("a"), Some("x\ty"));
+        assert_eq!(lang.get("b"), Some("x\ty"));
+        assert_eq!(lang.get("c"), Some("x\t#y"));
+    }
+}
@@ -1,13 +1,40 @@
 pub mod align;
+pub mod animate;
+pub mod chat;
 pub mod cmd_helper;
+pub mod command;
+pub mod compile;
 pub mod define;
 pub mod font;
-pub mod font_widths;
+pub mod format_code;
+pub mod font_source;
+pub mod glyph_cache;
+pub mod java;
+pub mod lang;
+pub mod metrics;
 pub mod pad;
+pub mod rawtext;
 pub mod render;
+pub mod screen;
+pub mod selector;
 pub mod utils;
+pub mod validate;
+pub mod width_table;
 
-pub use render::render;
+pub use render::{render, render_layout, render_layout_with_report, render_with_report, MissingGlyphError, RenderOutput};
+pub use font::MissingGlyphPolicy;
+pub use animate::{render_animation, AnimationError, AnimationFormat, AnimationOptions};
+pub use chat::{render_chat, wrap_message, ChatOptions};
+pub use screen::{render_screen, ScreenOptions, ScreenOutput};
 pub use align::{align_simple, get_line_width, cut_by_length};
 pub use pad::{pad, pad_with_format, pad_with_length};
-pub use cmd_helper::translate_tellraw;
+pub use cmd_helper::{resolve_rawtext, translate_tellraw, translate_tellraw_with_lang, MissingScorePolicy, TellrawContext};
+pub use compile::{compile_rawtext, compile_tellraw, CompileError};
+pub use command::{is_command_line, parse_command, Command, CommandError, RenderLayout, TitleChannel};
+pub use java::{java_to_formatted, java_to_rawtext, JavaImport, JavaWarning};
+pub use lang::LangFile;
+pub use width_table::WidthTable;
+pub use metrics::FontMetrics;
+pub use validate::{validate_tellraw, validate_value, Diagnostic, Severity};
+pub use selector::{MockEntity, Selector, SelectorError, World};
+pub use rawtext::{Component, RawText, RawTextError};
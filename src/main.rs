@@ -4,11 +4,136 @@ use actix_web::{
     HttpRequest,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Deserialize, Serialize, Debug)]
 struct RenderForm {
     mode: String,
     content: String,
+    // Language file name under `lang/`, e.g. `en_US`
+    #[serde(default)]
+    lang: Option<String>,
+    // Executor name shown by `say` / `me` in command mode
+    #[serde(default)]
+    sender: Option<String>,
+    // Named world fixture saved under `fixtures/`
+    #[serde(default)]
+    fixture: Option<String>,
+    // Per-request substitutions, applied on top of the fixture
+    #[serde(default)]
+    scores: Option<HashMap<String, HashMap<String, i32>>>,
+    #[serde(default)]
+    selectors: Option<HashMap<String, String>>,
+    // Player viewing the message; resolves `"name":"*"` scores
+    #[serde(default)]
+    viewer: Option<String>,
+    // `"empty"`, `"zero"` or `{"placeholder":"..."}`
+    #[serde(default)]
+    missing_score: Option<mcbe_text_impact::MissingScorePolicy>,
+    // `"tofu"` (default), `"space"` or `"error"` for characters the font cannot draw
+    #[serde(default)]
+    missing_glyph: Option<mcbe_text_impact::MissingGlyphPolicy>,
+    // Drop shadow under the text (default); `false` for sign-style text
+    #[serde(default)]
+    shadow: Option<bool>,
+    // Canvas fill as `[r, g, b, a]`; transparent when omitted
+    #[serde(default)]
+    background: Option<(u8, u8, u8, u8)>,
+    // `"gif"` or `"apng"` renders §k text animated instead of a still PNG
+    #[serde(default)]
+    animate: Option<mcbe_text_impact::AnimationFormat>,
+    #[serde(default)]
+    frames: Option<u32>,
+    // Fixed seed for reproducible frames
+    #[serde(default)]
+    seed: Option<u64>,
+    // Show `content` as the newest message of a chat panel instead of on its own
+    #[serde(default)]
+    chat: Option<ChatForm>,
+    // Place `content` on a game screen with the title, subtitle and actionbar positions
+    #[serde(default)]
+    screen: Option<ScreenForm>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct ChatForm {
+    // Earlier messages above `content`, oldest first, in the same mode
+    #[serde(default)]
+    history: Vec<String>,
+    #[serde(default)]
+    gui_scale: Option<u32>,
+    // Panel width in game pixels
+    #[serde(default)]
+    width: Option<i32>,
+    #[serde(default)]
+    max_lines: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct ScreenForm {
+    // Other commands on screen at the same time, e.g. the title for a subtitle;
+    // `content` comes last and replaces an earlier message on its channel
+    #[serde(default)]
+    messages: Vec<String>,
+    // Screen size in pixels
+    #[serde(default)]
+    viewport: Option<(u32, u32)>,
+    #[serde(default)]
+    gui_scale: Option<u32>,
+}
+
+impl ScreenForm {
+    fn options(&self) -> mcbe_text_impact::ScreenOptions {
+        let mut options = mcbe_text_impact::ScreenOptions::default();
+        if let Some((w, h)) = self.viewport {
+            let w = w.clamp(1, MAX_VIEWPORT.0);
+            options.viewport = (w, h.clamp(1, MAX_VIEWPORT.1).min(MAX_VIEWPORT_PIXELS / w));
+        }
+        if let Some(gui_scale) = self.gui_scale {
+            options.gui_scale = gui_scale.clamp(1, MAX_GUI_SCALE);
+        }
+        options
+    }
+}
+
+impl ChatForm {
+    fn options(&self) -> mcbe_text_impact::ChatOptions {
+        let mut options = mcbe_text_impact::ChatOptions::default();
+        if let Some(gui_scale) = self.gui_scale {
+            options.gui_scale = gui_scale.clamp(1, MAX_GUI_SCALE);
+        }
+        if let Some(width) = self.width {
+            options.width = width.clamp(MIN_CHAT_WIDTH, MAX_CHAT_WIDTH) * mcbe_text_impact::define::GAME_PIXEL;
+        }
+        if let Some(max_lines) = self.max_lines {
+            options.max_lines = max_lines.max(1);
+        }
+        options
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct CompileForm {
+    content: String,
+    // Resolve `(padN)` markers with `pad_with_format` before compiling
+    #[serde(default)]
+    pad: bool,
+}
+
+#[derive(Serialize)]
+struct CompileResponse {
+    json: String,
+}
+
+// Scoreboard, selector substitutions and mock entities saved on the server
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct WorldFixture {
+    #[serde(default)]
+    scores: HashMap<String, HashMap<String, i32>>,
+    #[serde(default)]
+    selectors: HashMap<String, String>,
+    #[serde(default)]
+    world: Option<mcbe_text_impact::World>,
 }
 
 #[derive(Serialize)]
@@ -16,10 +141,278 @@ struct ErrorResponse {
     error: String,
 }
 
+#[derive(Serialize)]
+struct ValidationErrorResponse {
+    error: String,
+    diagnostics: Vec<mcbe_text_impact::Diagnostic>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ValidateForm {
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ValidateResponse {
+    valid: bool,
+    diagnostics: Vec<mcbe_text_impact::Diagnostic>,
+}
+
 // Index served as static `web/index.html`
 
+const LANG_DIR: &str = "lang";
+const DEFAULT_LANG: &str = "en_US";
+const DEFAULT_SENDER: &str = "Steve";
+const FIXTURE_DIR: &str = "fixtures";
+const DEFAULT_FONT: &str = "font_png";
+const WIDTHS_FILE: &str = "widths.json";
+const MAX_FRAMES: u32 = 200;
+const ALLOW_FIXTURE_WRITES: &str = "--allow-fixture-writes";
+const MAX_GUI_SCALE: u32 = 8;
+const MIN_CHAT_WIDTH: i32 = 40;
+const MAX_CHAT_WIDTH: i32 = 1000;
+const MAX_VIEWPORT: (u32, u32) = (3840, 2160);
+// About 16 MB of RGBA per screen preview; taller viewports are cut to fit
+const MAX_VIEWPORT_PIXELS: u32 = 1920 * 1080 * 2;
+// Warnings beyond these limits are only logged, to keep the header a sane size
+const MAX_HEADER_WARNINGS: usize = 20;
+const MAX_WARNING_CHARS: usize = 200;
+
+// Font packs given on the command line, highest priority first, with the vanilla
+// `font_png` directory as the last fallback. Each entry may be a glyph directory,
+// a pack root with `manifest.json` or a `.mcpack` / `.zip` archive.
+// The font is opened once at startup; requests clone it and share its glyph cache.
+// The cell size is detected up front so HD packs render at their own resolution.
+// Alignment measures with `widths` (from `widths.json`) if given, otherwise with the font.
+struct FontConfig {
+    font: mcbe_text_impact::font::RuneFont,
+    widths: Option<mcbe_text_impact::WidthTable>,
+}
+
+impl FontConfig {
+    fn open(paths: &[String]) -> std::io::Result<Self> {
+        #[allow(unused_mut)]
+        let mut stack = mcbe_text_impact::font_source::FontStack::from_paths(paths)?;
+        // Without `font_png/` on disk the compiled-in copy is the last fallback
+        #[cfg(feature = "embed-font")]
+        if !paths.iter().any(|p| p == DEFAULT_FONT) {
+            stack.push(Box::new(mcbe_text_impact::font_source::EmbeddedSource));
+        }
+        let mut font = mcbe_text_impact::font::RuneFont::from_stack(stack);
+        // Never render below the vanilla 32px cell
+        font.set_cell_size(font.native_cell_size().max(mcbe_text_impact::define::LOGICAL_CELL));
+        Ok(Self { font, widths: None })
+    }
+
+    fn metrics(&self) -> &dyn mcbe_text_impact::FontMetrics {
+        match &self.widths {
+            Some(widths) => widths,
+            None => &self.font,
+        }
+    }
+}
+
+// Names used as file stems must not escape their directory
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// Load `lang/<name>.lang`; a missing file leaves translation keys untranslated
+fn load_lang(name: Option<&str>) -> Result<mcbe_text_impact::LangFile, String> {
+    let name = name.unwrap_or(DEFAULT_LANG);
+    if !is_valid_name(name) {
+        return Err(format!("Invalid language name '{}'", name));
+    }
+    let path = format!("{}/{}.lang", LANG_DIR, name);
+    if !std::path::Path::new(&path).exists() {
+        return Ok(mcbe_text_impact::LangFile::new());
+    }
+    mcbe_text_impact::LangFile::load(&path).map_err(|e| format!("Failed to load {}: {}", path, e))
+}
+
+fn fixture_path(name: &str) -> String {
+    format!("{}/{}.json", FIXTURE_DIR, name)
+}
+
+// Why a fixture could not be loaded
+enum FixtureError {
+    InvalidName(String),
+    NotFound(String),
+    Malformed(String),
+}
+
+impl FixtureError {
+    fn message(self) -> String {
+        match self {
+            FixtureError::InvalidName(e) | FixtureError::NotFound(e) | FixtureError::Malformed(e) => e,
+        }
+    }
+}
+
+fn load_fixture(name: &str) -> Result<WorldFixture, FixtureError> {
+    if !is_valid_name(name) {
+        return Err(FixtureError::InvalidName(format!("Invalid fixture name '{}'", name)));
+    }
+    let path = fixture_path(name);
+    let content = std::fs::read_to_string(&path).map_err(|_| FixtureError::NotFound(format!("Fixture '{}' not found", name)))?;
+    serde_json::from_str(&content).map_err(|e| FixtureError::Malformed(format!("Failed to parse {}: {}", path, e)))
+}
+
+// List saved fixture names
+async fn fixtures_list() -> Result<HttpResponse, Error> {
+    let mut names: Vec<String> = std::fs::read_dir(FIXTURE_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    if path.extension().and_then(|x| x.to_str()) != Some("json") {
+                        return None;
+                    }
+                    path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    Ok(HttpResponse::Ok().json(names))
+}
+
+async fn fixture_get(name: web::Path<String>) -> Result<HttpResponse, Error> {
+    match load_fixture(&name) {
+        Ok(fixture) => Ok(HttpResponse::Ok().json(fixture)),
+        Err(FixtureError::InvalidName(e)) => Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e })),
+        Err(FixtureError::NotFound(e)) => Ok(HttpResponse::NotFound().json(ErrorResponse { error: e })),
+        Err(FixtureError::Malformed(e)) => Ok(HttpResponse::UnprocessableEntity().json(ErrorResponse { error: e })),
+    }
+}
+
+// Without `--allow-fixture-writes` the server never writes to disk
+async fn fixture_put_disabled() -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Forbidden().json(ErrorResponse {
+        error: format!("Saving fixtures is disabled; start the server with {}", ALLOW_FIXTURE_WRITES),
+    }))
+}
+
+async fn fixture_put(name: web::Path<String>, body: web::Bytes) -> Result<HttpResponse, Error> {
+    if !is_valid_name(&name) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Invalid fixture name '{}'", name),
+        }));
+    }
+    let fixture: WorldFixture = match serde_json::from_slice(&body) {
+        Ok(f) => f,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid fixture: {}", e),
+            }));
+        }
+    };
+    let saved = std::fs::create_dir_all(FIXTURE_DIR)
+        .and_then(|_| std::fs::write(fixture_path(&name), serde_json::to_vec_pretty(&fixture).unwrap_or_default()));
+    match saved {
+        Ok(()) => Ok(HttpResponse::Ok().json(fixture)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to save fixture: {}", e),
+        })),
+    }
+}
+
+// Compile § formatted text into tellraw JSON
+async fn compile_post(body: web::Bytes, font: web::Data<FontConfig>) -> Result<HttpResponse, Error> {
+    let form: CompileForm = match serde_json::from_slice(&body) {
+        Ok(f) => f,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid JSON: {}", e),
+            }));
+        }
+    };
+    let content = if form.pad {
+        mcbe_text_impact::pad_with_format(font.metrics(), &form.content)
+    } else {
+        form.content
+    };
+    match mcbe_text_impact::compile_tellraw(&content) {
+        Ok(json) => Ok(HttpResponse::Ok().json(CompileResponse { json })),
+        Err(e) => Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e.to_string() })),
+    }
+}
+
+// Check a tellraw JSON payload and list every problem found
+async fn validate_post(body: web::Bytes) -> Result<HttpResponse, Error> {
+    let form: ValidateForm = match serde_json::from_slice(&body) {
+        Ok(f) => f,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid JSON: {}", e),
+            }));
+        }
+    };
+    let diagnostics = mcbe_text_impact::validate_tellraw(&form.content);
+    Ok(HttpResponse::Ok().json(ValidateResponse {
+        valid: !mcbe_text_impact::validate::has_errors(&diagnostics),
+        diagnostics,
+    }))
+}
+
+// Report which pack supplies each glyph page
+async fn font_pages(font: web::Data<FontConfig>) -> Result<HttpResponse, Error> {
+    let pages: std::collections::BTreeMap<String, String> = (0..=0xFF)
+        .filter_map(|page| font.font.page_origin(page).map(|origin| (format!("{:02X}", page), origin)))
+        .collect();
+    Ok(HttpResponse::Ok().json(pages))
+}
+
+// Turn `content` into § formatted text and the layout it is shown in, following the
+// form's mode; `Err` is the response to send back
+fn resolve_content(
+    form: &RenderForm,
+    ctx: &mcbe_text_impact::TellrawContext,
+    content: &str,
+    import_warnings: &mut Vec<mcbe_text_impact::JavaWarning>,
+) -> Result<(String, mcbe_text_impact::RenderLayout), Box<HttpResponse>> {
+    let mode = form.mode.as_str();
+    // Command lines (`tellraw @a {...}`, `titleraw @s actionbar {...}`, `say`, `me`) are
+    // also accepted in tellraw mode
+    let is_command = mode == "command" || (mode == "tellraw" && mcbe_text_impact::is_command_line(content));
+
+    if is_command {
+        match mcbe_text_impact::parse_command(content) {
+            Ok(command) => {
+                let sender = form.sender.as_deref().unwrap_or(DEFAULT_SENDER);
+                Ok((command.to_text(ctx, sender), command.layout()))
+            }
+            Err(e) => Err(Box::new(HttpResponse::BadRequest().json(ErrorResponse {
+                error: e.to_string(),
+            }))),
+        }
+    } else if mode == "java" {
+        match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(val) => {
+                let import = mcbe_text_impact::java_to_rawtext(&val);
+                import_warnings.extend(import.warnings);
+                Ok((ctx.resolve(&import.rawtext), mcbe_text_impact::RenderLayout::Chat))
+            }
+            Err(e) => Err(Box::new(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid JSON: {}", e),
+            }))),
+        }
+    } else if mode == "tellraw" {
+        match content.parse::<mcbe_text_impact::RawText>() {
+            Ok(rawtext) => Ok((ctx.resolve(&rawtext), mcbe_text_impact::RenderLayout::Chat)),
+            Err(e) => Err(Box::new(HttpResponse::BadRequest().json(ValidationErrorResponse {
+                error: e.to_string(),
+                diagnostics: mcbe_text_impact::validate_tellraw(content),
+            }))),
+        }
+    } else {
+        Ok((content.to_string(), mcbe_text_impact::RenderLayout::Chat))
+    }
+}
+
 // Handle render request
-async fn render_post(req: HttpRequest, body: web::Bytes) -> Result<HttpResponse, Error> {
+async fn render_post(req: HttpRequest, body: web::Bytes, font: web::Data<FontConfig>) -> Result<HttpResponse, Error> {
     // Log content-type for debugging
     let content_type = req
         .headers()
@@ -46,53 +439,166 @@ async fn render_post(req: HttpRequest, body: web::Bytes) -> Result<HttpResponse,
     }
 
     let mode = form.mode.as_str();
-    if mode != "text" && mode != "tellraw" {
+    if mode != "text" && mode != "tellraw" && mode != "command" && mode != "java" {
         return Ok(HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Mode must be 'text' or 'tellraw'".to_string(),
+            error: "Mode must be 'text', 'tellraw', 'command' or 'java'".to_string(),
         }));
     }
 
     let content = form.content.as_str();
 
-    // Prepare substitutions (empty for now)
-    let selectors_sub: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    let scores_sub: std::collections::HashMap<String, std::collections::HashMap<String, i32>> = std::collections::HashMap::new();
+    let lang = match load_lang(form.lang.as_deref()) {
+        Ok(lang) => lang,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e }));
+        }
+    };
 
-    let text_to_render = if mode == "tellraw" {
-        match serde_json::from_str::<serde_json::Value>(content) {
-            Ok(mut val) => {
-                let translated = mcbe_text_impact::translate_tellraw(&mut val, &selectors_sub, &scores_sub);
-                if translated.is_string() {
-                    translated.as_str().unwrap().to_string()
-                } else if let Some(arr) = translated.get("rawtext").and_then(|v| v.as_array()) {
-                    let mut out = String::new();
-                    for el in arr.iter() {
-                        if let Some(s) = el.get("text").and_then(|v| v.as_str()) {
-                            out.push_str(s);
-                        }
-                    }
-                    out
-                } else {
-                    serde_json::to_string(&translated).unwrap_or_default()
-                }
-            }
-            Err(_) => {
-                // If JSON parse fails, return error
-                return Ok(HttpResponse::BadRequest().body("Invalid JSON"));
+    // Prepare substitutions: saved fixture first, request maps override it
+    let mut fixture = match form.fixture.as_deref() {
+        Some(name) => match load_fixture(name) {
+            Ok(f) => f,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().json(ErrorResponse { error: e.message() }));
             }
+        },
+        None => WorldFixture::default(),
+    };
+    for (objective, scores) in form.scores.clone().unwrap_or_default() {
+        fixture.scores.entry(objective).or_default().extend(scores);
+    }
+    fixture.selectors.extend(form.selectors.clone().unwrap_or_default());
+
+    let mut ctx = mcbe_text_impact::TellrawContext::new(fixture.selectors, fixture.scores, lang);
+    ctx.world = fixture.world;
+    ctx.viewer = form.viewer.clone();
+    ctx.missing_score = form.missing_score.clone().unwrap_or_default();
+
+    // Features dropped while importing Java components, reported in a response header
+    let mut import_warnings = Vec::new();
+
+    let (text_to_render, layout) = match resolve_content(&form, &ctx, content, &mut import_warnings) {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(*response),
+    };
+
+    // Other messages shown with `content` go through the same conversion
+    let others: &[String] = match (&form.chat, &form.screen) {
+        (Some(_), Some(_)) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "'chat' and 'screen' cannot be combined; the screen view includes the chat panel".to_string(),
+            }));
         }
-    } else {
-        content.to_string()
+        (Some(chat), None) => &chat.history,
+        (None, Some(screen)) => &screen.messages,
+        (None, None) => &[],
     };
+    let mut messages = Vec::new();
+    for other in others {
+        match resolve_content(&form, &ctx, other, &mut import_warnings) {
+            Ok(resolved) => messages.push(resolved),
+            Err(response) => return Ok(*response),
+        }
+    }
+    messages.push((text_to_render.clone(), layout));
+    if form.chat.is_some() && messages.iter().any(|(_, l)| *l != mcbe_text_impact::RenderLayout::Chat) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "titleraw messages are not shown in chat".to_string(),
+        }));
+    }
 
-    // Call existing render function; font directory is `font_png`
-    let img = mcbe_text_impact::render("font_png", &text_to_render, None);
+    let mut rune_font = font.font.clone();
+    rune_font.set_missing_glyph_policy(form.missing_glyph.unwrap_or_default());
+    let mut options = mcbe_text_impact::render::SimulateOptions::default();
+    if form.shadow.unwrap_or(true) {
+        options.shadow = Some(mcbe_text_impact::render::ShadowOptions::default());
+    }
+    options.background = form.background;
+
+    // The chat panel and the screen view are always still images
+    if let Some(format) = form.animate.filter(|_| form.chat.is_none() && form.screen.is_none()) {
+        let mut animation = mcbe_text_impact::AnimationOptions {
+            format,
+            seed: form.seed,
+            ..Default::default()
+        };
+        if let Some(frames) = form.frames {
+            animation.frames = frames.clamp(1, MAX_FRAMES);
+        }
+        return match mcbe_text_impact::render_animation(rune_font, &text_to_render, layout, Some(options), &animation) {
+            Ok(buf) => Ok(HttpResponse::Ok().content_type(format.content_type()).body(buf)),
+            Err(e @ mcbe_text_impact::AnimationError::MissingGlyph(_)) => {
+                Ok(HttpResponse::UnprocessableEntity().json(ErrorResponse { error: e.to_string() }))
+            }
+            Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse { error: e.to_string() })),
+        };
+    }
+    // Overlap and clipping found by the screen view, reported in a response header
+    let mut layout_warnings = Vec::new();
+    let output = if let Some(chat) = &form.chat {
+        let texts: Vec<String> = messages.into_iter().map(|(text, _)| text).collect();
+        mcbe_text_impact::render_chat(rune_font, &texts, &chat.options(), Some(options))
+    } else if let Some(screen) = &form.screen {
+        mcbe_text_impact::render_screen(rune_font, &messages, &screen.options(), Some(options)).map(|screen| {
+            layout_warnings = screen.warnings();
+            mcbe_text_impact::RenderOutput { image: screen.image, missing: screen.missing }
+        })
+    } else {
+        mcbe_text_impact::render_layout_with_report(rune_font, &text_to_render, layout, Some(options))
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            return Ok(HttpResponse::UnprocessableEntity().json(ErrorResponse { error: e.to_string() }));
+        }
+    };
+    let img = output.image;
 
     // Convert RgbaImage to bytes
     let mut buf: Vec<u8> = Vec::new();
     img.write_to(&mut std::io::Cursor::new(&mut buf), image::ImageOutputFormat::Png).unwrap();
 
-    Ok(HttpResponse::Ok().content_type("image/png").body(buf))
+    let mut response = HttpResponse::Ok();
+    response.content_type("image/png");
+    if !import_warnings.is_empty() {
+        for w in &import_warnings {
+            log::warn!("Java import {}: {}", w.path, w.message);
+        }
+        let mut shown: Vec<mcbe_text_impact::JavaWarning> = import_warnings
+            .iter()
+            .take(MAX_HEADER_WARNINGS)
+            .map(|w| mcbe_text_impact::JavaWarning {
+                path: w.path.chars().take(MAX_WARNING_CHARS).collect(),
+                message: w.message.chars().take(MAX_WARNING_CHARS).collect(),
+            })
+            .collect();
+        if import_warnings.len() > MAX_HEADER_WARNINGS {
+            shown.push(mcbe_text_impact::JavaWarning {
+                path: "/".to_string(),
+                message: format!("{} more warnings omitted", import_warnings.len() - MAX_HEADER_WARNINGS),
+            });
+        }
+        // Header values must be ASCII, so non-ASCII characters use JSON `\uXXXX` escapes
+        let mut warnings = String::new();
+        for c in serde_json::to_string(&shown).unwrap_or_default().chars() {
+            if c.is_ascii() {
+                warnings.push(c);
+            } else {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    warnings.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+        response.insert_header(("X-Import-Warnings", warnings));
+    }
+    if !output.missing.is_empty() {
+        response.insert_header(("X-Missing-Glyphs", mcbe_text_impact::render::format_code_points(&output.missing)));
+    }
+    if !layout_warnings.is_empty() {
+        response.insert_header(("X-Layout-Warnings", serde_json::to_string(&layout_warnings).unwrap_or_default()));
+    }
+    Ok(response.body(buf))
 }
 
 #[actix_web::main]
@@ -101,17 +607,42 @@ async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix_web=debug");
     env_logger::init();
 
+    let mut font_paths: Vec<String> = std::env::args().skip(1).collect();
+    // Fixture writes are opt-in since any page open in the browser can reach the server
+    let allow_fixture_writes = font_paths.iter().any(|a| a == ALLOW_FIXTURE_WRITES);
+    font_paths.retain(|a| a != ALLOW_FIXTURE_WRITES);
+    if !font_paths.iter().any(|p| p == DEFAULT_FONT) && std::path::Path::new(DEFAULT_FONT).exists() {
+        font_paths.push(DEFAULT_FONT.to_string());
+    }
+
     // Check required directories
-    for dir in &["font_png", "web"] {
+    for dir in font_paths.iter().map(|p| p.as_str()).chain(["web"]) {
         let path = std::path::Path::new(dir);
         if !path.exists() {
             eprintln!("Error: Required directory '{}' not found", dir);
             std::process::exit(1);
         }
     }
+    if font_paths.is_empty() && !cfg!(feature = "embed-font") {
+        eprintln!("Error: Required directory '{}' not found", DEFAULT_FONT);
+        std::process::exit(1);
+    }
+
+    let mut font_config = match FontConfig::open(&font_paths) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: Cannot open font: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let preloaded = font_config.font.preload();
+    println!("Preloaded {} glyph pages", preloaded);
+    font_config.widths = mcbe_text_impact::WidthTable::load(std::path::Path::new(WIDTHS_FILE)).ok();
+    let font = web::Data::new(font_config);
 
-    let server = HttpServer::new(|| {
+    let server = HttpServer::new(move || {
         App::new()
+            .app_data(font.clone())
             .wrap(middleware::Logger::default())
             .wrap(
                 middleware::DefaultHeaders::new()
@@ -119,6 +650,7 @@ async fn main() -> std::io::Result<()> {
                     .add(("Access-Control-Allow-Origin", "*"))
                     .add(("Access-Control-Allow-Methods", "POST, GET, OPTIONS"))
                     .add(("Access-Control-Allow-Headers", "Content-Type"))
+                    .add(("Access-Control-Expose-Headers", "X-Import-Warnings, X-Missing-Glyphs, X-Layout-Warnings"))
                     // Disable cache
                     .add(("Cache-Control", "no-store, no-cache, must-revalidate, proxy-revalidate, max-age=0"))
                     .add(("Pragma", "no-cache"))
@@ -128,6 +660,16 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::scope("/api")
                     .route("/render", web::post().to(render_post))
+                    .route("/compile", web::post().to(compile_post))
+                    .route("/validate", web::post().to(validate_post))
+                    .route("/font/pages", web::get().to(font_pages))
+                    .route("/fixtures", web::get().to(fixtures_list))
+                    .route("/fixtures/{name}", web::get().to(fixture_get))
+                    .route("/fixtures/{name}", if allow_fixture_writes {
+                        web::put().to(fixture_put)
+                    } else {
+                        web::put().to(fixture_put_disabled)
+                    })
             )
             .service(
                 Files::new("/", "web")
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use crate::define::BOLD_PAD;
+use crate::font::RuneFont;
+use crate::render::FMT_Bold;
+use crate::width_table::WidthTable;
+
+// Character widths in logical units (32px cell) as used by `align`, `pad` and `render`.
+// Passing the `RuneFont` of the target pack makes alignment agree with its rendering.
+pub trait FontMetrics {
+    // Advance of a single character without the padding between characters
+    fn char_width(&self, ch: &str, bold: bool) -> i32;
+}
+
+impl FontMetrics for WidthTable {
+    fn char_width(&self, ch: &str, bold: bool) -> i32 {
+        let Some(c) = ch.chars().next() else { return 0 };
+        let width = self.width(c as u32) as i32;
+        if bold { width + BOLD_PAD } else { width }
+    }
+}
+
+impl FontMetrics for RuneFont {
+    fn char_width(&self, ch: &str, bold: bool) -> i32 {
+        if ch.is_empty() {
+            return 0;
+        }
+        self.logical_width(ch, if bold { FMT_Bold } else { 0 })
+    }
+}
+
+impl<T: FontMetrics + ?Sized> FontMetrics for Arc<T> {
+    fn char_width(&self, ch: &str, bold: bool) -> i32 {
+        (**self).char_width(ch, bold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::get_line_width;
+    use crate::font_source::{DirSource, GlyphSource};
+    use crate::render::{SimulateOptions, TellRawSimulator};
+
+    // The vanilla pages for Basic Latin and for 中, to keep generating the table quick
+    struct VanillaPages(DirSource);
+
+    impl GlyphSource for VanillaPages {
+        fn read(&self, file_name: &str) -> Option<Vec<u8>> {
+            ["glyph_00.png", "glyph_4E.png"].contains(&file_name).then(|| self.0.read(file_name))?
+        }
+
+        fn name(&self) -> String {
+            format!("{} (00, 4E)", self.0.name())
+        }
+    }
+
+    #[test]
+    fn width_table_and_font_agree() {
+        let font = RuneFont::from_source(Box::new(VanillaPages(DirSource::new(std::path::Path::new("font_png")))));
+        let table = WidthTable::generate(&font);
+        let cases = [(" ", 12), ("a", 12), ("中", 22), ("hello world", 168), ("§lab§r c", 64), ("§oa", 20)];
+        for (text, width) in cases {
+            assert_eq!(get_line_width(&font, text), width, "{}", text);
+            assert_eq!(get_line_width(&table, text), width, "{}", text);
+        }
+
+        let mut simulator = TellRawSimulator::new(font, SimulateOptions::default());
+        for text in ["hello world", "§lab§r c\n§o中a"] {
+            let measured = simulator.measure(text);
+            assert_eq!(simulator.measure_with(&table, text), measured, "{}", text);
+            let widest = measured.iter().max().copied().unwrap_or(0);
+            assert_eq!(simulator.render(text).width(), widest as u32, "{}", text);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::define::CHAR_HORIZON_PADDING;
 use crate::align::{get_line_width, get_char_width};
+use crate::metrics::FontMetrics;
 use crate::utils::solve_xy;
 
 // Check if all numbers have the same parity
@@ -12,7 +13,7 @@ pub fn check_same_parity(c: &[i32]) -> bool {
 }
 
 // Resolve padding values
-pub fn resolve(c: &[i32]) -> Option<Vec<(i32, i32)>> {
+pub fn resolve(metrics: &dyn FontMetrics, c: &[i32]) -> Option<Vec<(i32, i32)>> {
     if c.is_empty() {
         return None;
     }
@@ -33,7 +34,7 @@ pub fn resolve(c: &[i32]) -> Option<Vec<(i32, i32)>> {
         
         for &ci in c {
             let di = width - ci;
-            if let Some((x, y)) = solve_xy(CHAR_HORIZON_PADDING, get_line_width("§l ") + CHAR_HORIZON_PADDING, di) {
+            if let Some((x, y)) = solve_xy(CHAR_HORIZON_PADDING, get_line_width(metrics, "§l ") + CHAR_HORIZON_PADDING, di) {
                 // Remove the assertion that was causing the panic
                 res.push((x, y));
             } else {
@@ -50,9 +51,9 @@ pub fn resolve(c: &[i32]) -> Option<Vec<(i32, i32)>> {
     }
 }
 
-pub fn pad(texts: &[String]) -> Vec<String> {
-    let cs: Vec<i32> = texts.iter().map(|t| get_line_width(t)).collect();
-    let res = resolve(&cs).expect("Failed to resolve padding");
+pub fn pad(metrics: &dyn FontMetrics, texts: &[String]) -> Vec<String> {
+    let cs: Vec<i32> = texts.iter().map(|t| get_line_width(metrics, t)).collect();
+    let res = resolve(metrics, &cs).expect("Failed to resolve padding");
     let pads: Vec<String> = res.iter().map(|(ns, nb)| {
         let mut pad = "§r".to_string();
         if *ns > 0 {
@@ -151,13 +152,13 @@ where
     }
 }
 
-pub fn pad_with_format(text: &str) -> String {
-    Padder::new(text, pad).execute()
+pub fn pad_with_format(metrics: &dyn FontMetrics, text: &str) -> String {
+    Padder::new(text, |texts: &[String]| pad(metrics, texts)).execute()
 }
 
-pub fn pad_with_length(length: i32, padder: &str, round: bool) -> String {
+pub fn pad_with_length(metrics: &dyn FontMetrics, length: i32, padder: &str, round: bool) -> String {
     let length = length + CHAR_HORIZON_PADDING;
-    let char_width = get_char_width(padder, false) + CHAR_HORIZON_PADDING;
+    let char_width = get_char_width(metrics, padder, false) + CHAR_HORIZON_PADDING;
     if !round {
         padder.repeat((length / char_width) as usize)
     } else {
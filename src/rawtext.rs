@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+// Typed model of a tellraw / titleraw message body
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreRef {
+    pub name: String,
+    pub objective: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TranslateWith {
+    Strings(Vec<String>),
+    RawText(RawText),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Component {
+    Text {
+        text: String,
+    },
+    Translate {
+        translate: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        with: Option<TranslateWith>,
+    },
+    Score {
+        score: ScoreRef,
+    },
+    Selector {
+        selector: String,
+    },
+    RawText {
+        rawtext: Vec<Component>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawText {
+    pub rawtext: Vec<Component>,
+}
+
+#[derive(Debug, Error)]
+pub enum RawTextError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Malformed element at '{path}': {message}")]
+    Malformed { path: String, message: String },
+}
+
+fn malformed(path: &str, message: impl Into<String>) -> RawTextError {
+    RawTextError::Malformed {
+        path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+        message: message.into(),
+    }
+}
+
+fn expect_str<'a>(value: &'a Value, path: &str) -> Result<&'a str, RawTextError> {
+    value.as_str().ok_or_else(|| malformed(path, "expected a string"))
+}
+
+impl Component {
+    pub fn text(text: &str) -> Self {
+        Component::Text { text: text.to_string() }
+    }
+
+    pub fn translate(key: &str, with: Option<TranslateWith>) -> Self {
+        Component::Translate { translate: key.to_string(), with }
+    }
+
+    pub fn score(name: &str, objective: &str) -> Self {
+        Component::Score {
+            score: ScoreRef {
+                name: name.to_string(),
+                objective: objective.to_string(),
+            },
+        }
+    }
+
+    pub fn selector(selector: &str) -> Self {
+        Component::Selector { selector: selector.to_string() }
+    }
+
+    // Parse one rawtext element; `path` is the JSON pointer of `value` used in errors
+    pub fn parse(value: &Value, path: &str) -> Result<Self, RawTextError> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| malformed(path, "expected an object"))?;
+
+        if let Some(text) = obj.get("text") {
+            let text = expect_str(text, &format!("{}/text", path))?;
+            Ok(Component::text(text))
+        } else if let Some(translate) = obj.get("translate") {
+            let key = expect_str(translate, &format!("{}/translate", path))?;
+            let with = match obj.get("with") {
+                Some(with) => Some(TranslateWith::parse(with, &format!("{}/with", path))?),
+                None => None,
+            };
+            Ok(Component::translate(key, with))
+        } else if let Some(score) = obj.get("score") {
+            let score_path = format!("{}/score", path);
+            let score = score
+                .as_object()
+                .ok_or_else(|| malformed(&score_path, "expected an object"))?;
+            let name = score
+                .get("name")
+                .ok_or_else(|| malformed(&score_path, "missing 'name'"))?;
+            let objective = score
+                .get("objective")
+                .ok_or_else(|| malformed(&score_path, "missing 'objective'"))?;
+            Ok(Component::score(
+                expect_str(name, &format!("{}/name", score_path))?,
+                expect_str(objective, &format!("{}/objective", score_path))?,
+            ))
+        } else if let Some(selector) = obj.get("selector") {
+            let selector = expect_str(selector, &format!("{}/selector", path))?;
+            Ok(Component::selector(selector))
+        } else if let Some(rawtext) = obj.get("rawtext") {
+            Ok(Component::RawText {
+                rawtext: parse_components(rawtext, &format!("{}/rawtext", path))?,
+            })
+        } else {
+            Err(malformed(
+                path,
+                "expected one of 'text', 'translate', 'score', 'selector' or 'rawtext'",
+            ))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+impl TranslateWith {
+    pub fn parse(value: &Value, path: &str) -> Result<Self, RawTextError> {
+        if let Some(arr) = value.as_array() {
+            // Numbers are accepted as arguments and shown as written
+            let strings = arr
+                .iter()
+                .enumerate()
+                .map(|(i, v)| match v {
+                    Value::Number(n) => Ok(n.to_string()),
+                    _ => expect_str(v, &format!("{}/{}", path, i)).map(|s| s.to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(TranslateWith::Strings(strings))
+        } else if value.is_object() {
+            Ok(TranslateWith::RawText(RawText::parse_at(value, path)?))
+        } else {
+            Err(malformed(path, "expected a string array or a rawtext object"))
+        }
+    }
+}
+
+fn parse_components(value: &Value, path: &str) -> Result<Vec<Component>, RawTextError> {
+    let arr = value
+        .as_array()
+        .ok_or_else(|| malformed(path, "expected an array"))?;
+    arr.iter()
+        .enumerate()
+        .map(|(i, v)| Component::parse(v, &format!("{}/{}", path, i)))
+        .collect()
+}
+
+impl RawText {
+    pub fn new(rawtext: Vec<Component>) -> Self {
+        Self { rawtext }
+    }
+
+    pub fn push(&mut self, component: Component) -> &mut Self {
+        self.rawtext.push(component);
+        self
+    }
+
+    // A bare JSON string is accepted as a message of plain text
+    pub fn parse(value: &Value) -> Result<Self, RawTextError> {
+        if let Value::String(text) = value {
+            return Ok(Self::new(vec![Component::text(text)]));
+        }
+        Self::parse_at(value, "")
+    }
+
+    fn parse_at(value: &Value, path: &str) -> Result<Self, RawTextError> {
+        let obj: &Map<String, Value> = value
+            .as_object()
+            .ok_or_else(|| malformed(path, "expected an object"))?;
+        let rawtext = obj
+            .get("rawtext")
+            .ok_or_else(|| malformed(path, "missing 'rawtext'"))?;
+        Ok(Self {
+            rawtext: parse_components(rawtext, &format!("{}/rawtext", path))?,
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, RawTextError> {
+        let value: Value = serde_json::from_str(json)?;
+        Self::parse(&value)
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl std::str::FromStr for RawText {
+    type Err = RawTextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_json(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_path(json: &str) -> String {
+        match RawText::from_json(json) {
+            Err(RawTextError::Malformed { path, .. }) => path,
+            other => panic!("expected a malformed error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bare_string_is_plain_text() {
+        assert_eq!(RawText::from_json("\"hello\"").unwrap(), RawText::new(vec![Component::text("hello")]));
+    }
+
+    #[test]
+    fn numeric_with_arguments() {
+        let rawtext = RawText::from_json(r#"{"rawtext":[{"translate":"%s-%s","with":[1,"a"]}]}"#).unwrap();
+        let with = Some(TranslateWith::Strings(vec!["1".to_string(), "a".to_string()]));
+        assert_eq!(rawtext.rawtext, vec![Component::translate("%s-%s", with)]);
+    }
+
+    #[test]
+    fn error_paths() {
+        assert_eq!(error_path("[]"), "/");
+        assert_eq!(error_path("{}"), "/");
+        assert_eq!(error_path(r#"{"rawtext":{}}"#), "/rawtext");
+        assert_eq!(error_path(r#"{"rawtext":[{"text":1}]}"#), "/rawtext/0/text");
+        assert_eq!(error_path(r#"{"rawtext":[{"score":{"name":"a"}}]}"#), "/rawtext/0/score");
+        assert_eq!(error_path(r#"{"rawtext":[{"translate":"k","with":[true]}]}"#), "/rawtext/0/with/0");
+        assert_eq!(error_path(r#"{"rawtext":[{"rawtext":[{}]}]}"#), "/rawtext/0/rawtext/0");
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(matches!(RawText::from_json("{"), Err(RawTextError::Json(_))));
+    }
+}
@@ -2,17 +2,52 @@ use std::collections::HashMap;
 use image::{Rgba, RgbaImage};
 use crate::font::RgbaMatrix;
 use crate::define::{ITALIC_CHAR_HORIZON_PADDING, CHAR_HORIZON_PADDING};
-use crate::font::{Font, RuneFont};
+use thiserror::Error;
+use crate::font::{is_invisible, Font, MissingGlyphPolicy, RuneFont};
+use crate::align::get_text_line_widths;
+use crate::command::RenderLayout;
+use crate::format_code::{FormatScanner, Style};
+use crate::metrics::FontMetrics;
+use crate::utils::{blend_image, blend_pixel, SplitMix64};
 
+#[allow(non_upper_case_globals)]
 pub const FMT_Obfuscated: u32 = 1 << 8;
+#[allow(non_upper_case_globals)]
 pub const FMT_Bold: u32 = 1 << 9;
+#[allow(non_upper_case_globals)]
 pub const FMT_Italic: u32 = 1 << 10;
 
+// Drop shadow drawn under the text, like the client does for chat and titles
+#[derive(Debug, Clone)]
+pub struct ShadowOptions {
+    // Logical units; one game pixel is 4
+    pub offset: (i32, i32),
+    // Shadow color is the text color times `darken`...
+    pub darken: f32,
+    // ...unless the format code has its own shadow color here
+    pub colors: HashMap<String, (u8, u8, u8, u8)>,
+}
+
+impl Default for ShadowOptions {
+    fn default() -> Self {
+        Self {
+            offset: (4, 4),
+            darken: 0.25,
+            colors: HashMap::new(),
+        }
+    }
+}
+
+// Paddings are logical units (a 32px glyph cell) and scale with the font's cell size.
+// No shadow by default, like sign-style text; set `shadow` for the client's chat look.
 #[derive(Debug, Clone)]
 pub struct SimulateOptions {
     pub font_horizon_padding: i32,
     pub line_padding: i32,
     pub color_mapping: HashMap<String, (u8, u8, u8, u8)>,
+    pub shadow: Option<ShadowOptions>,
+    // Canvas fill under the text; `None` keeps it transparent
+    pub background: Option<(u8, u8, u8, u8)>,
 }
 
 impl Default for SimulateOptions {
@@ -51,21 +86,50 @@ impl Default for SimulateOptions {
             font_horizon_padding: CHAR_HORIZON_PADDING,
             line_padding: 6,
             color_mapping,
+            shadow: None,
+            background: None,
         }
     }
 }
 
+// Rendered image plus the characters the font had no glyph for, in order of appearance
+#[derive(Debug, Clone)]
+pub struct RenderOutput {
+    pub image: RgbaImage,
+    pub missing: Vec<char>,
+}
+
+#[derive(Debug, Error)]
+#[error("No glyph for {}", format_code_points(.missing))]
+pub struct MissingGlyphError {
+    pub missing: Vec<char>,
+}
+
+// `U+1F600, U+E123`
+pub fn format_code_points(chars: &[char]) -> String {
+    chars.iter().map(|c| format!("U+{:04X}", *c as u32)).collect::<Vec<_>>().join(", ")
+}
+
+// Characters `§k` text cycles through, like the client's obfuscated text
+// Obfuscated characters are swapped for these or for others on their own glyph page, so
+// wide glyphs (CJK, ...) have candidates of their width too
+const OBFUSCATION_POOL: std::ops::RangeInclusive<char> = '!'..='~';
+
 pub struct TellRawSimulator {
     font: RuneFont,
     options: SimulateOptions,
+    // Obfuscation candidates per format and glyph page (`None` for `OBFUSCATION_POOL`),
+    // grouped by pixel width
+    obfuscation_pool: HashMap<(u32, Option<u32>), HashMap<u32, Vec<String>>>,
 }
 
 impl TellRawSimulator {
     pub fn new(font: RuneFont, options: SimulateOptions) -> Self {
-        Self { font, options }
+        Self { font, options, obfuscation_pool: HashMap::new() }
     }
 
-    // Draw font onto canvas
+    // Draw font onto canvas, blending source-over; uncolored glyphs are alpha masks tinted
+    // with `color`
     fn draw(
         &self,
         canvas: &mut RgbaMatrix,
@@ -77,30 +141,19 @@ impl TellRawSimulator {
         let w = patch.width() as i32;
         let (start_x, start_y) = pos;
         
-        if !patch.colored {
-            for y in 0..h {
-                for x in 0..w {
-                    if start_y + y < canvas.height() as i32 && start_x + x < canvas.width() as i32 {
-                        let pixel = patch.mat.get_pixel(x as u32, y as u32);
-                        let alpha = pixel[3] as f32 / 255.0;
-                        let new_pixel = Rgba([
-                            (color.0 as f32 * alpha) as u8,
-                            (color.1 as f32 * alpha) as u8,
-                            (color.2 as f32 * alpha) as u8,
-                            (color.3 as f32 * alpha) as u8,
-                        ]);
-                        canvas.put_pixel((start_x + x) as u32, (start_y + y) as u32, new_pixel);
-                    }
-                }
-            }
-        } else {
-            for y in 0..h {
-                for x in 0..w {
-                    if start_y + y < canvas.height() as i32 && start_x + x < canvas.width() as i32 {
-                        let pixel = patch.mat.get_pixel(x as u32, y as u32);
-                        canvas.put_pixel((start_x + x) as u32, (start_y + y) as u32, *pixel);
-                    }
+        for y in 0..h {
+            for x in 0..w {
+                let (cx, cy) = (start_x + x, start_y + y);
+                if cx < 0 || cy < 0 || cy >= canvas.height() as i32 || cx >= canvas.width() as i32 {
+                    continue;
                 }
+                let pixel = patch.mat.get_pixel(x as u32, y as u32);
+                let src = if patch.colored {
+                    *pixel
+                } else {
+                    Rgba([color.0, color.1, color.2, (color.3 as u32 * pixel[3] as u32 / 255) as u8])
+                };
+                blend_pixel(canvas.get_pixel_mut(cx as u32, cy as u32), &src);
             }
         }
     }
@@ -119,47 +172,38 @@ impl TellRawSimulator {
         color
     }
 
+    fn get_shadow_color(&self, shadow: &ShadowOptions, fmt: u32) -> (u8, u8, u8, u8) {
+        if fmt & 0x7F != 0 {
+            let code = char::from_u32(fmt & 0x7F).unwrap_or('0').to_string();
+            if let Some(&c) = shadow.colors.get(&code) {
+                return c;
+            }
+        }
+        let (r, g, b, a) = self.get_color(fmt);
+        let darken = |v: u8| (v as f32 * shadow.darken) as u8;
+        (darken(r), darken(g), darken(b), a)
+    }
+
+    // Format bits of `style`: the color code in the low 7 bits plus the `FMT_*` flags
+    fn style_fmt(style: &Style) -> u32 {
+        let flags = [(style.bold, FMT_Bold), (style.italic, FMT_Italic), (style.obfuscated, FMT_Obfuscated)];
+        let color = style.color.map_or(0, |code| code as u32);
+        flags.iter().filter(|(on, _)| *on).fold(color, |fmt, (_, flag)| fmt | flag)
+    }
+
     // Split format and text
     fn split_format_and_text(&self, mix: &str) -> (Vec<Vec<String>>, Vec<Vec<u32>>) {
-        let mut current_fmt = 0;
-        let lines: Vec<&str> = mix.split('\n').collect();
+        let mut style = Style::default();
         let mut out_text = Vec::new();
         let mut out_fmt = Vec::new();
-        
-        for line in lines {
-            let mut text = Vec::new();
-            let mut fmt = Vec::new();
-            let mut is_fmt = false;
-            
-            for ch in line.chars() {
-                if ch == '§' {
-                    if is_fmt {
-                        text.push(ch.to_string());
-                        fmt.push(current_fmt);
-                        is_fmt = false;
-                    } else {
-                        is_fmt = true;
-                    }
-                } else if is_fmt {
-                    match ch {
-                        'r' => current_fmt = 0,
-                        'l' => current_fmt |= FMT_Bold,
-                        'o' => current_fmt |= FMT_Italic,
-                        'k' => current_fmt |= FMT_Obfuscated,
-                        '0'..='9' | 'a'..='u' => {
-                            current_fmt = (current_fmt & 0xFF80) | (ch as u32);
-                        }
-                        _ => {
-                            text.push(ch.to_string());
-                            fmt.push(current_fmt);
-                        }
-                    }
-                    is_fmt = false;
-                } else {
-                    text.push(ch.to_string());
-                    fmt.push(current_fmt);
-                }
-            }
+
+        for line in mix.split('\n') {
+            let mut scanner = FormatScanner::with_style(line, style);
+            let (text, fmt): (Vec<String>, Vec<u32>) = scanner
+                .by_ref()
+                .filter_map(|token| Some((token.ch?.to_string(), Self::style_fmt(&token.style))))
+                .unzip();
+            style = scanner.style();
             out_text.push(text);
             out_fmt.push(fmt);
         }
@@ -177,10 +221,26 @@ impl TellRawSimulator {
         }
         
         if last_fmt & FMT_Italic != 0 {
-            total_width += ITALIC_CHAR_HORIZON_PADDING;
+            total_width += self.font.to_px(ITALIC_CHAR_HORIZON_PADDING);
         }
         
-        total_width + (line.len() as i32 - 1).max(0) * self.options.font_horizon_padding
+        total_width + (line.len() as i32 - 1).max(0) * self.font.to_px(self.options.font_horizon_padding)
+    }
+
+    // Width of each line in logical units, whatever the resolution of the font pages
+    pub fn measure(&self, text: &str) -> Vec<i32> {
+        self.measure_with(&self.font, text)
+    }
+
+    // Width of each line in logical units as `metrics` measures it, the same way `align`
+    // and `pad` do, so text padded with `metrics` can be checked against the rendering
+    pub fn measure_with(&self, metrics: &dyn FontMetrics, text: &str) -> Vec<i32> {
+        let padding = self.options.font_horizon_padding - CHAR_HORIZON_PADDING;
+        get_text_line_widths(metrics, text)
+            .into_iter()
+            .zip(self.split_format_and_text(text).0)
+            .map(|(width, line)| width + (line.len() as i32 - 1).max(0) * padding)
+            .collect()
     }
 
     // Shear image for italic effect
@@ -210,31 +270,198 @@ impl TellRawSimulator {
     }
 
     pub fn render(&mut self, text: &str) -> RgbaImage {
+        self.render_output(text).image
+    }
+
+    // Render and report missing glyphs; fails under `MissingGlyphPolicy::Error`
+    pub fn render_with_report(&mut self, text: &str) -> Result<RenderOutput, MissingGlyphError> {
+        let output = self.render_output(text);
+        if self.font.missing_glyph_policy() == MissingGlyphPolicy::Error && !output.missing.is_empty() {
+            return Err(MissingGlyphError { missing: output.missing });
+        }
+        Ok(output)
+    }
+
+    // `frames` images of `text` where every `§k` character is swapped for a random glyph of
+    // the same width on each frame; the same `seed` gives the same frames.
+    // Fails under `MissingGlyphPolicy::Error` like `render_with_report`, obfuscated
+    // characters included.
+    pub fn render_frames(&mut self, text: &str, frames: u32, seed: u64) -> Result<Vec<RgbaImage>, MissingGlyphError> {
+        let (lines, fmts) = self.split_format_and_text(text);
+        let mut rng = SplitMix64::new(seed);
+        let mut missing: Vec<char> = Vec::new();
+        let mut images = Vec::new();
+        for _ in 0..frames {
+            let mut frame_lines = lines.clone();
+            let mut frame_fmts = fmts.clone();
+            for (line, fmt) in frame_lines.iter_mut().zip(frame_fmts.iter_mut()) {
+                for (c, f) in line.iter_mut().zip(fmt.iter_mut()) {
+                    if *f & FMT_Obfuscated == 0 {
+                        continue;
+                    }
+                    *f &= !FMT_Obfuscated;
+                    if self.font.get_font(c, *f & 0xFF80).missing {
+                        missing.extend(c.chars().next().filter(|ch| !missing.contains(ch)));
+                    }
+                    if let Some(swap) = self.obfuscated_glyph(c, *f, &mut rng) {
+                        *c = swap;
+                    }
+                }
+            }
+            let output = self.render_split(&frame_lines, &frame_fmts);
+            for ch in output.missing {
+                if !missing.contains(&ch) {
+                    missing.push(ch);
+                }
+            }
+            images.push(output.image);
+        }
+        if self.font.missing_glyph_policy() == MissingGlyphPolicy::Error && !missing.is_empty() {
+            return Err(MissingGlyphError { missing });
+        }
+        Ok(images)
+    }
+
+    // Glyphs of the pool `page` selects, by width in format `key`; missing and blank
+    // glyphs are left out
+    fn obfuscation_candidates(&mut self, key: u32, page: Option<u32>) -> &HashMap<u32, Vec<String>> {
+        let font = &self.font;
+        self.obfuscation_pool.entry((key, page)).or_insert_with(|| {
+            let chars: Vec<char> = match page {
+                None => OBFUSCATION_POOL.collect(),
+                Some(page) => (page * 256..(page + 1) * 256).filter_map(char::from_u32).collect(),
+            };
+            let mut by_width: HashMap<u32, Vec<String>> = HashMap::new();
+            for ch in chars.into_iter().filter(|ch| !ch.is_whitespace() && !ch.is_control() && !is_invisible(*ch)) {
+                let ch = ch.to_string();
+                let glyph = font.get_font(&ch, key);
+                if !glyph.missing && glyph.width() > 0 {
+                    by_width.entry(glyph.width()).or_default().push(ch);
+                }
+            }
+            by_width
+        })
+    }
+
+    // Random glyph as wide as `c` in format `fmt`, from the basic pool and `c`'s own page
+    fn obfuscated_glyph(&mut self, c: &str, fmt: u32, rng: &mut SplitMix64) -> Option<String> {
+        let key = fmt & 0xFF80;
+        let width = self.font.get_font(c, key).width();
+        let page = c
+            .chars()
+            .next()
+            .filter(|ch| (*ch as u32) < 0x10000 && !OBFUSCATION_POOL.contains(ch))
+            .map(|ch| ch as u32 >> 8);
+        let mut candidates: Vec<String> = self.obfuscation_candidates(key, None).get(&width).cloned().unwrap_or_default();
+        if let Some(page) = page {
+            for ch in self.obfuscation_candidates(key, Some(page)).get(&width).into_iter().flatten() {
+                if !candidates.contains(ch) {
+                    candidates.push(ch.clone());
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[rng.next_index(candidates.len())].clone())
+    }
+
+    fn render_output(&mut self, text: &str) -> RenderOutput {
         let (lines, fmts) = self.split_format_and_text(text);
+        self.render_split(&lines, &fmts)
+    }
+
+    fn render_split(&mut self, lines: &[Vec<String>], fmts: &[Vec<u32>]) -> RenderOutput {
+        let mut missing: Vec<char> = Vec::new();
         let max_width = lines.iter()
             .zip(fmts.iter())
             .map(|(line, fmt)| self.get_line_width(line, fmt))
             .max()
             .unwrap_or(0) as u32;
         
-        let height = (lines.len() as u32 * 31 + (lines.len() as u32 - 1).max(0) * self.options.line_padding as u32) as u32;
-        let mut mat = RgbaImage::new(max_width, height);
+        let glyph_height = self.font.glyph_height();
+        let line_padding = self.font.to_px(self.options.line_padding) as u32;
+        // Glyphs taller than the line (full `default8.png` cells) may reach below the last one
+        let overflow = lines
+            .last()
+            .zip(fmts.last())
+            .into_iter()
+            .flat_map(|(line, fmt)| line.iter().zip(fmt.iter()))
+            .map(|(c, &f)| self.font.get_font(c, f & 0xFF80).mat.height())
+            .max()
+            .unwrap_or(0)
+            .saturating_sub(glyph_height);
+        let height = lines.len() as u32 * glyph_height + lines.len().saturating_sub(1) as u32 * line_padding + overflow;
+        let text_layer = self.draw_lines(lines, fmts, (max_width, height), None, &mut missing);
+
+        // The shadow is a second pass in shadow colors, placed under the text at the offset
+        let mut layers = Vec::new();
+        let (mut dx, mut dy) = (0, 0);
+        if let Some(shadow) = self.options.shadow.clone() {
+            (dx, dy) = (self.font.to_px(shadow.offset.0), self.font.to_px(shadow.offset.1));
+            let shadow_layer = self.draw_lines(lines, fmts, (max_width, height), Some(&shadow), &mut Vec::new());
+            layers.push((shadow_layer, (dx.max(0), dy.max(0))));
+        }
+        layers.push((text_layer, ((-dx).max(0), (-dy).max(0))));
+
+        let (w, h) = (max_width + dx.unsigned_abs(), height + dy.unsigned_abs());
+        let mut mat = match self.options.background {
+            Some((r, g, b, a)) => RgbaImage::from_pixel(w, h, Rgba([r, g, b, a])),
+            None => RgbaImage::new(w, h),
+        };
+        for (layer, (x, y)) in &layers {
+            blend_image(&mut mat, layer, *x as i64, *y as i64);
+        }
+        RenderOutput { image: mat, missing }
+    }
+
+    // One layer of text; with `shadow` every glyph is drawn in its shadow color
+    fn draw_lines(
+        &mut self,
+        lines: &[Vec<String>],
+        fmts: &[Vec<u32>],
+        size: (u32, u32),
+        shadow: Option<&ShadowOptions>,
+        missing: &mut Vec<char>,
+    ) -> RgbaImage {
+        let glyph_height = self.font.glyph_height();
+        let line_padding = self.font.to_px(self.options.line_padding) as u32;
+        let char_padding = self.font.to_px(self.options.font_horizon_padding);
+        let mut mat = RgbaImage::new(size.0, size.1);
         
         for (line_i, (line, fmt)) in lines.iter().zip(fmts.iter()).enumerate() {
-            let start_y = (line_i as u32 * (31 + self.options.line_padding as u32)) as i32;
+            let start_y = (line_i as u32 * (glyph_height + line_padding)) as i32;
+            // Italic runs are sheared over the full height of their tallest glyph
+            let row_height = line
+                .iter()
+                .zip(fmt.iter())
+                .map(|(c, &f)| self.font.get_font(c, f & 0xFF80).mat.height())
+                .fold(glyph_height, u32::max);
             let mut start_x = 0;
             let mut italic_start_x = -1;
             
             for (i, (c, &f)) in line.iter().zip(fmt.iter()).enumerate() {
                 let pos = (start_x, start_y);
                 let patch = self.font.get_font(c, f & 0xFF80);
-                self.draw(&mut mat, &patch, pos, self.get_color(f));
+                match shadow {
+                    // Colored glyphs (emoji, ...) keep their shape but are darkened as a whole
+                    Some(shadow) if patch.colored => {
+                        let mut dark = (*patch).clone();
+                        dark.colored = false;
+                        self.draw(&mut mat, &dark, pos, self.get_shadow_color(shadow, 0));
+                    }
+                    Some(shadow) => self.draw(&mut mat, &patch, pos, self.get_shadow_color(shadow, f)),
+                    None => self.draw(&mut mat, &patch, pos, self.get_color(f)),
+                }
+                if patch.missing {
+                    missing.extend(c.chars().next().filter(|ch| !missing.contains(ch)));
+                }
                 
                 if f & FMT_Italic != 0 && italic_start_x == -1 {
                     italic_start_x = start_x;
                 }
                 
-                start_x += patch.width() as i32 + self.options.font_horizon_padding;
+                start_x += patch.width() as i32 + char_padding;
                 
                 if italic_start_x == -1 {
                     continue;
@@ -244,27 +471,25 @@ impl TellRawSimulator {
                     continue;
                 }
                 
-                let italic_end_x = start_x - self.options.font_horizon_padding;
+                let italic_end_x = start_x - char_padding;
                 let italic_region = image::imageops::crop_imm(
                     &mat,
                     italic_start_x.max(0) as u32,
                     start_y as u32,
                     (italic_end_x - italic_start_x.max(0)) as u32,
-                    31,
+                    row_height,
                 ).to_image();
                 
                 let italic_mat = self.italic(&italic_region);
-                let w = italic_mat.width();
-                let paste_x = (italic_start_x - 4).max(0) as u32;
+                let paste_x = (italic_start_x - self.font.to_px(4)).max(0);
                 
-                for y in 0..31 {
-                    for x in 0..w {
-                        if paste_x + x < mat.width() && start_y + y < mat.height() as i32 {
-                            let pixel = italic_mat.get_pixel(x, y as u32);
-                            mat.put_pixel(paste_x + x, (start_y + y) as u32, *pixel);
-                        }
+                // Clear the upright run, then blend the sheared one so neighbours survive
+                for y in start_y as u32..(start_y as u32 + row_height).min(mat.height()) {
+                    for x in italic_start_x.max(0) as u32..(italic_end_x.max(0) as u32).min(mat.width()) {
+                        mat.put_pixel(x, y, Rgba([0, 0, 0, 0]));
                     }
                 }
+                blend_image(&mut mat, &italic_mat, paste_x as i64, start_y as i64);
                 
                 italic_start_x = -1;
             }
@@ -274,10 +499,140 @@ impl TellRawSimulator {
     }
 }
 
+// `img_dir_path` may be a glyph directory, an unpacked pack root or a `.mcpack` / `.zip`
 pub fn render(img_dir_path: &str, text: &str, options: Option<SimulateOptions>) -> RgbaImage {
-    let mut simulator = TellRawSimulator::new(
-        RuneFont::new(img_dir_path),
-        options.unwrap_or_default(),
-    );
+    let font = RuneFont::from_pack(img_dir_path).unwrap_or_else(|_| RuneFont::new(img_dir_path));
+    render_with_font(font, text, options)
+}
+
+pub fn render_with_font(font: RuneFont, text: &str, options: Option<SimulateOptions>) -> RgbaImage {
+    let mut simulator = TellRawSimulator::new(font, options.unwrap_or_default());
     simulator.render(text)
+}
+
+pub fn render_with_report(font: RuneFont, text: &str, options: Option<SimulateOptions>) -> Result<RenderOutput, MissingGlyphError> {
+    let mut simulator = TellRawSimulator::new(font, options.unwrap_or_default());
+    simulator.render_with_report(text)
+}
+// Titles are drawn at twice the chat text size; the other layouts use chat size
+pub fn layout_scale(layout: RenderLayout) -> u32 {
+    match layout {
+        RenderLayout::Title => 2,
+        RenderLayout::Chat | RenderLayout::Subtitle | RenderLayout::Actionbar => 1,
+    }
+}
+
+pub fn render_layout(font: RuneFont, text: &str, layout: RenderLayout, options: Option<SimulateOptions>) -> RgbaImage {
+    scale_layout(render_with_font(font, text, options), layout)
+}
+
+pub fn render_layout_with_report(
+    font: RuneFont,
+    text: &str,
+    layout: RenderLayout,
+    options: Option<SimulateOptions>,
+) -> Result<RenderOutput, MissingGlyphError> {
+    let output = render_with_report(font, text, options)?;
+    Ok(RenderOutput {
+        image: scale_layout(output.image, layout),
+        missing: output.missing,
+    })
+}
+
+pub(crate) fn scale_layout(img: RgbaImage, layout: RenderLayout) -> RgbaImage {
+    let scale = layout_scale(layout);
+    if scale == 1 {
+        return img;
+    }
+    image::imageops::resize(&img, img.width() * scale, img.height() * scale, image::imageops::FilterType::Nearest)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::define::GAME_PIXEL;
+
+    fn render_text(text: &str, options: SimulateOptions) -> RgbaImage {
+        TellRawSimulator::new(RuneFont::new("font_png"), options).render(text)
+    }
+
+    fn with_shadow() -> SimulateOptions {
+        SimulateOptions { shadow: Some(ShadowOptions::default()), ..Default::default() }
+    }
+
+    // Last opaque pixel in reading order, so nothing of the text lies below and right of it
+    fn last_opaque(image: &RgbaImage) -> (u32, u32) {
+        image
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[3] == 255)
+            .map(|(x, y, _)| (x, y))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn shadow_is_one_game_pixel_down_right_and_darkened() {
+        // One game pixel at the default 32px cell
+        let d = GAME_PIXEL as u32;
+        for (text, color) in [("I", (255, 255, 255)), ("§cI", (255, 85, 85))] {
+            let plain = render_text(text, SimulateOptions::default());
+            let shadowed = render_text(text, with_shadow());
+            assert_eq!(shadowed.dimensions(), (plain.width() + d, plain.height() + d), "{}", text);
+
+            let (x, y) = last_opaque(&plain);
+            let darken = |v: u8| (v as f32 * 0.25) as u8;
+            assert_eq!(*shadowed.get_pixel(x, y), Rgba([color.0, color.1, color.2, 255]), "{}", text);
+            assert_eq!(*shadowed.get_pixel(x + d, y + d), Rgba([darken(color.0), darken(color.1), darken(color.2), 255]), "{}", text);
+        }
+    }
+
+    #[test]
+    fn translucent_pixels_blend_source_over() {
+        let mut pixel = Rgba([0, 0, 255, 255]);
+        blend_pixel(&mut pixel, &Rgba([255, 0, 0, 128]));
+        assert_eq!(pixel, Rgba([128, 0, 127, 255]));
+
+        let mut pixel = Rgba([0, 0, 255, 128]);
+        blend_pixel(&mut pixel, &Rgba([255, 0, 0, 128]));
+        assert_eq!(pixel, Rgba([170, 0, 85, 192]));
+
+        // Nothing under the source leaves it as is, nothing over the destination too
+        let mut pixel = Rgba([0, 0, 0, 0]);
+        blend_pixel(&mut pixel, &Rgba([10, 20, 30, 40]));
+        assert_eq!(pixel, Rgba([10, 20, 30, 40]));
+        blend_pixel(&mut pixel, &Rgba([255, 255, 255, 0]));
+        assert_eq!(pixel, Rgba([10, 20, 30, 40]));
+    }
+
+    #[test]
+    fn text_and_shadow_are_composited_over_the_background() {
+        let background = (0, 0, 255, 255);
+        let mut options = SimulateOptions { background: Some(background), ..with_shadow() };
+        options.color_mapping.insert("c".to_string(), (255, 0, 0, 128));
+        let image = render_text("§cII", options);
+        let plain = render_text("§cII", SimulateOptions::default());
+
+        let blue = Rgba([0, 0, 255, 255]);
+        let shadow = Rgba([63, 0, 0, 128]);
+        let text = Rgba([255, 0, 0, 128]);
+        let over = |layers: &[Rgba<u8>]| {
+            let mut pixel = blue;
+            layers.iter().for_each(|layer| blend_pixel(&mut pixel, layer));
+            pixel
+        };
+        let d = GAME_PIXEL as u32;
+        let is_text = |x: u32, y: u32| x < plain.width() && y < plain.height() && plain.get_pixel(x, y)[3] > 0;
+        let mut overlaps = 0;
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let under = x >= d && y >= d && is_text(x - d, y - d);
+            let expected = match (is_text(x, y), under) {
+                (true, true) => over(&[shadow, text]),
+                (true, false) => over(&[text]),
+                (false, true) => over(&[shadow]),
+                (false, false) => blue,
+            };
+            overlaps += (is_text(x, y) && under) as usize;
+            assert_eq!(*pixel, expected, "({}, {})", x, y);
+        }
+        assert!(overlaps > 0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,304 @@
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use crate::chat::{render_chat, wrap_message, ChatOptions};
+use crate::command::RenderLayout;
+use crate::define::{GAME_PIXEL, GLYPH_HEIGHT, LOGICAL_CELL};
+use crate::font::RuneFont;
+use crate::render::{layout_scale, MissingGlyphError, RenderOutput, SimulateOptions, TellRawSimulator};
+use crate::utils::{blend_image, blend_pixel};
+
+// Title, subtitle, actionbar and chat composed on a screen of a given size, to check
+// the messages for overlap and clipping. Offsets are logical units like `SimulateOptions`.
+
+// Vertical position of a channel; every channel but chat is centered horizontally
+#[derive(Debug, Clone, Copy)]
+pub enum Anchor {
+    // Top of the text this far below the screen centre, negative is above
+    Center(i32),
+    // Bottom of the text this far above the screen bottom
+    Bottom(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelOptions {
+    // Multiple of the chat text size
+    pub scale: u32,
+    pub anchor: Anchor,
+    pub background: Option<(u8, u8, u8, u8)>,
+    // Background margin around the text, before `scale`
+    pub padding: i32,
+}
+
+impl ChannelOptions {
+    fn new(layout: RenderLayout, anchor: Anchor) -> Self {
+        Self {
+            scale: layout_scale(layout),
+            anchor,
+            background: Some((0, 0, 0, 128)),
+            padding: 2 * GAME_PIXEL,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenOptions {
+    // Screen pixels
+    pub viewport: (u32, u32),
+    // Screen pixels per game pixel
+    pub gui_scale: u32,
+    pub title: ChannelOptions,
+    pub subtitle: ChannelOptions,
+    pub actionbar: ChannelOptions,
+    // The panel's `gui_scale` is replaced by the screen's
+    pub chat: ChatOptions,
+    // Bottom edge of the chat panel above the screen bottom
+    pub chat_bottom: i32,
+}
+
+impl Default for ScreenOptions {
+    fn default() -> Self {
+        Self {
+            viewport: (854, 480),
+            gui_scale: 2,
+            title: ChannelOptions::new(RenderLayout::Title, Anchor::Center(-28 * GAME_PIXEL)),
+            subtitle: ChannelOptions::new(RenderLayout::Subtitle, Anchor::Center(4 * GAME_PIXEL)),
+            // Clear of the hotbar and the health and hunger rows above it
+            actionbar: ChannelOptions::new(RenderLayout::Actionbar, Anchor::Bottom(59 * GAME_PIXEL)),
+            chat: ChatOptions::default(),
+            chat_bottom: 40 * GAME_PIXEL,
+        }
+    }
+}
+
+// Screen pixel rectangle a message takes, background included
+#[derive(Debug, Clone, Serialize)]
+pub struct Placement {
+    pub layout: RenderLayout,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    // Part of it falls outside the viewport
+    pub clipped: bool,
+}
+
+impl Placement {
+    fn overlaps(&self, other: &Placement) -> bool {
+        self.x < other.x + other.width as i32
+            && other.x < self.x + self.width as i32
+            && self.y < other.y + other.height as i32
+            && other.y < self.y + self.height as i32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenOutput {
+    pub image: RgbaImage,
+    pub missing: Vec<char>,
+    pub placements: Vec<Placement>,
+    // Pairs of messages whose rectangles intersect
+    pub overlaps: Vec<(RenderLayout, RenderLayout)>,
+}
+
+impl ScreenOutput {
+    // `"subtitle overlaps title"`, `"actionbar is clipped by the viewport"`, ...
+    pub fn warnings(&self) -> Vec<String> {
+        let clipped = self
+            .placements
+            .iter()
+            .filter(|p| p.clipped)
+            .map(|p| format!("{} is clipped by the viewport", p.layout.name()));
+        let overlaps = self.overlaps.iter().map(|(a, b)| format!("{} overlaps {}", b.name(), a.name()));
+        clipped.chain(overlaps).collect()
+    }
+}
+
+// Glyphs the font cannot draw, gathered over every message on screen
+#[derive(Default)]
+struct Missing {
+    chars: Vec<char>,
+    failed: bool,
+}
+
+impl Missing {
+    fn add(&mut self, result: Result<RenderOutput, MissingGlyphError>) -> RgbaImage {
+        let (image, missing) = match result {
+            Ok(output) => (output.image, output.missing),
+            // Keep going so the error lists every missing glyph
+            Err(e) => {
+                self.failed = true;
+                (RgbaImage::new(0, 0), e.missing)
+            }
+        };
+        for c in missing {
+            if !self.chars.contains(&c) {
+                self.chars.push(c);
+            }
+        }
+        image
+    }
+}
+
+// `text` with every line centered, drawn at `scale` times the chat size. Lines wider
+// than `width` wrap like the game wraps titles that do not fit the screen.
+fn render_block(
+    mut font: RuneFont,
+    text: &str,
+    (gui_scale, scale): (u32, u32),
+    width: i32,
+    simulate: &SimulateOptions,
+    missing: &mut Missing,
+) -> RgbaImage {
+    font.set_cell_size(gui_scale * scale * LOGICAL_CELL / GAME_PIXEL as u32);
+    // Each line is drawn on its own, carrying formatting over from the line before
+    let lines = wrap_message(&font, text, width);
+    let line_height = font.to_px(9 * GAME_PIXEL) as u32;
+    let text_top = font.to_px((9 * GAME_PIXEL - GLYPH_HEIGHT) / 2);
+
+    let mut simulator = TellRawSimulator::new(font, simulate.clone());
+    let images: Vec<RgbaImage> = lines.iter().map(|line| missing.add(simulator.render_with_report(line))).collect();
+    let width = images.iter().map(|img| img.width()).max().unwrap_or(0);
+
+    let mut mat = RgbaImage::new(width, images.len() as u32 * line_height);
+    for (i, img) in images.iter().enumerate() {
+        let x = (width - img.width()) / 2;
+        let y = (i as u32 * line_height) as i32 + text_top;
+        blend_image(&mut mat, img, x as i64, y as i64);
+    }
+    mat
+}
+
+fn fill_rect(mat: &mut RgbaImage, (x, y, w, h): (i32, i32, u32, u32), (r, g, b, a): (u8, u8, u8, u8)) {
+    let color = Rgba([r, g, b, a]);
+    for py in y.max(0)..(y + h as i32).min(mat.height() as i32) {
+        for px in x.max(0)..(x + w as i32).min(mat.width() as i32) {
+            blend_pixel(mat.get_pixel_mut(px as u32, py as u32), &color);
+        }
+    }
+}
+
+// Compose `messages` on one screen. A later message for the same title channel replaces
+// an earlier one, like the game does; chat messages stack in the chat panel.
+pub fn render_screen(
+    mut font: RuneFont,
+    messages: &[(String, RenderLayout)],
+    options: &ScreenOptions,
+    simulate: Option<SimulateOptions>,
+) -> Result<ScreenOutput, MissingGlyphError> {
+    // Sizes on screen follow the viewport, not the pack's resolution
+    font.set_cell_size(LOGICAL_CELL);
+    let simulate = simulate.unwrap_or_default();
+    let (vw, vh) = options.viewport;
+    let gui_scale = options.gui_scale.max(1);
+    // Logical units to screen pixels
+    let to_screen = |logical: i32| logical * gui_scale as i32 / GAME_PIXEL;
+
+    let mut mat = match simulate.background {
+        Some((r, g, b, a)) => RgbaImage::from_pixel(vw, vh, Rgba([r, g, b, a])),
+        None => RgbaImage::new(vw, vh),
+    };
+    let line_options = SimulateOptions { background: None, ..simulate };
+    let mut missing = Missing::default();
+    let mut placements = Vec::new();
+
+    let chat: Vec<String> = messages
+        .iter()
+        .filter(|(_, layout)| *layout == RenderLayout::Chat)
+        .map(|(text, _)| text.clone())
+        .collect();
+    if !chat.is_empty() {
+        let chat_options = ChatOptions { gui_scale, ..options.chat.clone() };
+        let panel = missing.add(render_chat(font.clone(), &chat, &chat_options, Some(line_options.clone())));
+        let y = vh as i32 - to_screen(options.chat_bottom) - panel.height() as i32;
+        blend_image(&mut mat, &panel, 0, y as i64);
+        placements.push((RenderLayout::Chat, (0, y, panel.width(), panel.height())));
+    }
+
+    for (layout, channel) in [
+        (RenderLayout::Title, &options.title),
+        (RenderLayout::Subtitle, &options.subtitle),
+        (RenderLayout::Actionbar, &options.actionbar),
+    ] {
+        let Some((text, _)) = messages.iter().rev().find(|(_, l)| *l == layout) else { continue };
+        let scale = channel.scale.max(1);
+        // Room across the screen in the block's own logical units, inside the background
+        let wrap_width = vw as i32 * GAME_PIXEL / (gui_scale * scale) as i32 - 2 * channel.padding;
+        let block = render_block(font.clone(), text, (gui_scale, scale), wrap_width.max(1), &line_options, &mut missing);
+        let padding = to_screen(channel.padding * scale as i32);
+        let (w, h) = (block.width() + 2 * padding as u32, block.height() + 2 * padding as u32);
+        let x = (vw as i32 - w as i32) / 2;
+        let y = match channel.anchor {
+            Anchor::Center(offset) => vh as i32 / 2 + to_screen(offset) - padding,
+            Anchor::Bottom(offset) => vh as i32 - to_screen(offset) - block.height() as i32 - padding,
+        };
+        if let Some(background) = channel.background {
+            fill_rect(&mut mat, (x, y, w, h), background);
+        }
+        blend_image(&mut mat, &block, (x + padding) as i64, (y + padding) as i64);
+        placements.push((layout, (x, y, w, h)));
+    }
+
+    if missing.failed {
+        return Err(MissingGlyphError { missing: missing.chars });
+    }
+
+    let placements: Vec<Placement> = placements
+        .into_iter()
+        .map(|(layout, (x, y, width, height))| Placement {
+            layout,
+            x,
+            y,
+            width,
+            height,
+            clipped: x < 0 || y < 0 || x + width as i32 > vw as i32 || y + height as i32 > vh as i32,
+        })
+        .collect();
+    let mut overlaps = Vec::new();
+    for (i, a) in placements.iter().enumerate() {
+        for b in &placements[i + 1..] {
+            if a.overlaps(b) {
+                overlaps.push((a.layout, b.layout));
+            }
+        }
+    }
+    Ok(ScreenOutput { image: mat, missing: missing.chars, placements, overlaps })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(messages: &[(&str, RenderLayout)], viewport: (u32, u32)) -> ScreenOutput {
+        let messages: Vec<(String, RenderLayout)> = messages.iter().map(|(text, layout)| (text.to_string(), *layout)).collect();
+        let options = ScreenOptions { viewport, ..ScreenOptions::default() };
+        render_screen(RuneFont::new("font_png"), &messages, &options, None).unwrap()
+    }
+
+    fn placement(output: &ScreenOutput, layout: RenderLayout) -> &Placement {
+        output.placements.iter().find(|p| p.layout == layout).unwrap()
+    }
+
+    #[test]
+    fn lines_split_at_newlines() {
+        let one = screen(&[("title", RenderLayout::Title)], (854, 480));
+        let two = screen(&[("title\n§lsecond", RenderLayout::Title)], (854, 480));
+        let (one, two) = (placement(&one, RenderLayout::Title), placement(&two, RenderLayout::Title));
+        assert!(two.height > one.height);
+    }
+
+    #[test]
+    fn long_titles_wrap_to_the_screen() {
+        let title = "a long title that is far too wide for a small screen";
+        let output = screen(&[(title, RenderLayout::Title)], (400, 480));
+        let wide = screen(&[(title, RenderLayout::Title)], (3840, 480));
+        let (title, wide) = (placement(&output, RenderLayout::Title), placement(&wide, RenderLayout::Title));
+        assert!(wide.width > 400 && title.width <= 400 && title.height > wide.height);
+        assert!(output.warnings().is_empty());
+    }
+
+    #[test]
+    fn overlapping_messages_are_reported() {
+        let output = screen(&[("title\n\n\n\n", RenderLayout::Title), ("subtitle", RenderLayout::Subtitle)], (854, 480));
+        assert_eq!(output.warnings(), vec!["subtitle overlaps title"]);
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::utils::SplitMix64;
+
+// Target selectors evaluated against a user supplied list of mock entities
+
+fn default_entity_type() -> String {
+    "player".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockEntity {
+    pub name: String,
+    #[serde(rename = "type", default = "default_entity_type")]
+    pub entity_type: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub scores: HashMap<String, i32>,
+}
+
+impl MockEntity {
+    pub fn player(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            entity_type: default_entity_type(),
+            tags: Vec::new(),
+            scores: HashMap::new(),
+        }
+    }
+
+    pub fn is_player(&self) -> bool {
+        normalize_type(&self.entity_type) == "player"
+    }
+}
+
+// Entities are listed nearest first, which is the order `@p` and `c=` use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct World {
+    #[serde(default)]
+    pub entities: Vec<MockEntity>,
+    // Name of the entity running the command (`@s`, `@initiator`)
+    #[serde(default)]
+    pub executor: Option<String>,
+    // Seed used by `@r`
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SelectorError {
+    #[error("Unknown selector variable '{0}'")]
+    UnknownVariable(String),
+    #[error("Unbalanced brackets in selector '{0}'")]
+    Unbalanced(String),
+    #[error("Unsupported selector argument '{0}'")]
+    UnknownArgument(String),
+    #[error("Invalid value '{value}' for selector argument '{key}'")]
+    InvalidValue { key: String, value: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorBase {
+    AllPlayers,
+    NearestPlayer,
+    RandomPlayer,
+    Executor,
+    Entities,
+    Initiator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreRange {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl ScoreRange {
+    pub fn parse(s: &str) -> Option<Self> {
+        let parse_bound = |b: &str| -> Option<Option<i32>> {
+            if b.is_empty() {
+                Some(None)
+            } else {
+                b.parse().ok().map(Some)
+            }
+        };
+        if let Some((min, max)) = s.split_once("..") {
+            Some(Self { min: parse_bound(min)?, max: parse_bound(max)? })
+        } else {
+            let v: i32 = s.parse().ok()?;
+            Some(Self { min: Some(v), max: Some(v) })
+        }
+    }
+
+    pub fn contains(&self, v: i32) -> bool {
+        self.min.is_none_or(|min| v >= min) && self.max.is_none_or(|max| v <= max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Tag { tag: String, negate: bool },
+    Name { name: String, negate: bool },
+    Type { entity_type: String, negate: bool },
+    Score { objective: String, range: ScoreRange, negate: bool },
+}
+
+impl Filter {
+    fn matches(&self, entity: &MockEntity) -> bool {
+        match self {
+            // `tag=` matches entities without tags, `tag=!` entities with any tag
+            Filter::Tag { tag, negate } if tag.is_empty() => entity.tags.is_empty() != *negate,
+            Filter::Tag { tag, negate } => entity.tags.iter().any(|t| t == tag) != *negate,
+            Filter::Name { name, negate } => (entity.name == *name) != *negate,
+            Filter::Type { entity_type, negate } => {
+                (normalize_type(&entity.entity_type) == normalize_type(entity_type)) != *negate
+            }
+            Filter::Score { objective, range, negate } => match entity.scores.get(objective) {
+                Some(&v) => range.contains(v) != *negate,
+                None => false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    pub base: SelectorBase,
+    pub filters: Vec<Filter>,
+    pub count: Option<i32>,
+}
+
+fn normalize_type(t: &str) -> &str {
+    t.strip_prefix("minecraft:").unwrap_or(t)
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(s)
+}
+
+fn split_negation(s: &str) -> (&str, bool) {
+    match s.strip_prefix('!') {
+        Some(rest) => (rest.trim(), true),
+        None => (s, false),
+    }
+}
+
+// Split on top level commas, ignoring commas inside `{...}` and quotes
+fn split_args(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut in_quote = false;
+    let mut start = 0;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '"' => in_quote = !in_quote,
+            '{' if !in_quote => depth += 1,
+            '}' if !in_quote => depth -= 1,
+            ',' if !in_quote && depth == 0 => {
+                out.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(s[start..].trim());
+    out.into_iter().filter(|a| !a.is_empty()).collect()
+}
+
+impl Selector {
+    pub fn parse(s: &str) -> Result<Self, SelectorError> {
+        let s = s.trim();
+        let (var, args) = match s.find('[') {
+            Some(i) => {
+                let args = s[i + 1..]
+                    .strip_suffix(']')
+                    .ok_or_else(|| SelectorError::Unbalanced(s.to_string()))?;
+                (&s[..i], Some(args))
+            }
+            None => (s, None),
+        };
+
+        let base = match var {
+            "@a" => SelectorBase::AllPlayers,
+            "@p" => SelectorBase::NearestPlayer,
+            "@r" => SelectorBase::RandomPlayer,
+            "@s" => SelectorBase::Executor,
+            "@e" => SelectorBase::Entities,
+            "@initiator" => SelectorBase::Initiator,
+            _ => return Err(SelectorError::UnknownVariable(var.to_string())),
+        };
+
+        let mut selector = Self { base, filters: Vec::new(), count: None };
+        for arg in args.map(split_args).unwrap_or_default() {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| SelectorError::UnknownArgument(arg.to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || SelectorError::InvalidValue { key: key.to_string(), value: value.to_string() };
+            match key {
+                "tag" => {
+                    let (tag, negate) = split_negation(value);
+                    selector.filters.push(Filter::Tag { tag: unquote(tag).to_string(), negate });
+                }
+                "name" => {
+                    let (name, negate) = split_negation(value);
+                    selector.filters.push(Filter::Name { name: unquote(name).to_string(), negate });
+                }
+                "type" => {
+                    let (entity_type, negate) = split_negation(value);
+                    selector.filters.push(Filter::Type { entity_type: entity_type.to_string(), negate });
+                }
+                "c" => selector.count = Some(value.parse().map_err(|_| invalid())?),
+                "scores" => {
+                    let inner = value
+                        .strip_prefix('{')
+                        .and_then(|v| v.strip_suffix('}'))
+                        .ok_or_else(invalid)?;
+                    for score in split_args(inner) {
+                        let (objective, range) = score.split_once('=').ok_or_else(invalid)?;
+                        let (range, negate) = split_negation(range.trim());
+                        selector.filters.push(Filter::Score {
+                            objective: unquote(objective.trim()).to_string(),
+                            range: ScoreRange::parse(range).ok_or_else(invalid)?,
+                            negate,
+                        });
+                    }
+                }
+                _ => return Err(SelectorError::UnknownArgument(key.to_string())),
+            }
+        }
+        Ok(selector)
+    }
+
+    pub fn select<'a>(&self, world: &'a World) -> Vec<&'a MockEntity> {
+        let executor = || {
+            world
+                .executor
+                .as_ref()
+                .and_then(|name| world.entities.iter().find(|e| &e.name == name))
+        };
+
+        let candidates: Vec<&MockEntity> = match self.base {
+            SelectorBase::Executor | SelectorBase::Initiator => executor().into_iter().collect(),
+            SelectorBase::Entities => world.entities.iter().collect(),
+            _ => world.entities.iter().filter(|e| e.is_player()).collect(),
+        };
+        let mut matched: Vec<&MockEntity> = candidates
+            .into_iter()
+            .filter(|e| self.filters.iter().all(|f| f.matches(e)))
+            .collect();
+
+        let default_count = match self.base {
+            SelectorBase::NearestPlayer | SelectorBase::RandomPlayer => Some(1),
+            _ => None,
+        };
+
+        if self.base == SelectorBase::RandomPlayer {
+            // Fisher-Yates with the world seed so previews are reproducible
+            let mut rng = SplitMix64::new(world.seed);
+            for i in (1..matched.len()).rev() {
+                let j = rng.next_index(i + 1);
+                matched.swap(i, j);
+            }
+        }
+
+        match self.count.or(default_count) {
+            Some(c) if c >= 0 => matched.truncate(c as usize),
+            // Negative counts take the farthest entities first
+            Some(c) => {
+                matched.reverse();
+                matched.truncate(c.unsigned_abs() as usize);
+            }
+            None => {}
+        }
+        matched
+    }
+
+    // Comma-joined names as printed by a rawtext `selector` component
+    pub fn resolve_names(&self, world: &World) -> String {
+        self.select(world)
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(seed: u64) -> World {
+        let mut entities: Vec<MockEntity> = ["Alex", "Steve", "Noor", "Kai", "Mia"].iter().map(|n| MockEntity::player(n)).collect();
+        entities[1].tags.push("admin".to_string());
+        entities[2].scores.insert("kills".to_string(), 5);
+        entities.push(MockEntity { entity_type: "minecraft:zombie".to_string(), ..MockEntity::player("Zombie") });
+        World { entities, executor: Some("Kai".to_string()), seed }
+    }
+
+    fn names(selector: &str, world: &World) -> String {
+        Selector::parse(selector).unwrap().resolve_names(world)
+    }
+
+    #[test]
+    fn splitmix64_matches_reference_output() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+    }
+
+    #[test]
+    fn random_player_is_deterministic_per_seed() {
+        for seed in 0..20 {
+            let picks: Vec<String> = (0..3).map(|_| names("@r", &world(seed))).collect();
+            assert!(picks.iter().all(|p| p == &picks[0]));
+            assert_ne!(picks[0], "Zombie");
+        }
+        let all: std::collections::HashSet<String> = (0..50).map(|seed| names("@r", &world(seed))).collect();
+        assert!(all.len() > 1, "every seed picked the same player");
+        assert_eq!(names("@r[c=5]", &world(7)).split(", ").count(), 5);
+    }
+
+    #[test]
+    fn filters_and_counts() {
+        let world = world(0);
+        assert_eq!(names("@a", &world), "Alex, Steve, Noor, Kai, Mia");
+        assert_eq!(names("@p", &world), "Alex");
+        assert_eq!(names("@s", &world), "Kai");
+        assert_eq!(names("@a[tag=admin]", &world), "Steve");
+        assert_eq!(names("@a[tag=!admin,c=2]", &world), "Alex, Noor");
+        assert_eq!(names("@a[scores={kills=3..}]", &world), "Noor");
+        assert_eq!(names("@a[c=-1]", &world), "Mia");
+        assert_eq!(names("@e[type=zombie]", &world), "Zombie");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Selector::parse("@x").unwrap_err(), SelectorError::UnknownVariable("@x".to_string()));
+        assert!(matches!(Selector::parse("@a[c=1"), Err(SelectorError::Unbalanced(_))));
+        assert!(matches!(Selector::parse("@a[c=two]"), Err(SelectorError::InvalidValue { .. })));
+        assert!(matches!(Selector::parse("@a[dx=1]"), Err(SelectorError::UnknownArgument(_))));
+    }
+}
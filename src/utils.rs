@@ -121,4 +121,63 @@ pub fn solve_xy(s: i32, b: i32, c: i32) -> Option<(i32, i32)> {
     } else {
         None
     }
+}
+
+// Small deterministic PRNG (SplitMix64), enough for picking mock entities and glyphs
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform index in `0..n`; `n` must be non-zero
+    pub fn next_index(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// Source-over blend of `src` onto `dst`, both with straight (non-premultiplied) alpha
+pub fn blend_pixel(dst: &mut image::Rgba<u8>, src: &image::Rgba<u8>) {
+    let sa = src[3] as u32;
+    if sa == 0 {
+        return;
+    }
+    if sa == 255 {
+        *dst = *src;
+        return;
+    }
+    let da = dst[3] as u32;
+    // Output alpha and colors scaled by 255 * 255 to stay in integers
+    let oa = sa * 255 + da * (255 - sa);
+    if oa == 0 {
+        *dst = image::Rgba([0, 0, 0, 0]);
+        return;
+    }
+    for c in 0..3 {
+        let v = src[c] as u32 * sa * 255 + dst[c] as u32 * da * (255 - sa);
+        dst[c] = ((v + oa / 2) / oa) as u8;
+    }
+    dst[3] = ((oa + 127) / 255) as u8;
+}
+
+// Blend `src` onto `dst` with its top left corner at `(x, y)`, clipped to `dst`
+pub fn blend_image(dst: &mut image::RgbaImage, src: &image::RgbaImage, x: i64, y: i64) {
+    for (sx, sy, pixel) in src.enumerate_pixels() {
+        let (dx, dy) = (x + sx as i64, y + sy as i64);
+        if dx >= 0 && dy >= 0 && dx < dst.width() as i64 && dy < dst.height() as i64 {
+            blend_pixel(dst.get_pixel_mut(dx as u32, dy as u32), pixel);
+        }
+    }
 }
\ No newline at end of file
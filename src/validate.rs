@@ -0,0 +1,287 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::format_code::Style;
+use crate::selector::Selector;
+
+// Semantic checks for tellraw payloads, reported with JSON pointer paths
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub severity: Severity,
+    pub message: String,
+    // Position in the source text, only known for syntax errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+const CONTENT_KEYS: [&str; 5] = ["text", "translate", "score", "selector", "rawtext"];
+
+// Escape a key for use as a JSON pointer segment
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+// The message root is reported as `/`, like `RawTextError`
+fn display_path(path: &str) -> String {
+    if path.is_empty() { "/".to_string() } else { path.to_string() }
+}
+
+struct Validator {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn push(&mut self, path: &str, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            path: display_path(path),
+            severity,
+            message,
+            line: None,
+            column: None,
+        });
+    }
+
+    fn error(&mut self, path: &str, message: String) {
+        self.push(path, Severity::Error, message);
+    }
+
+    fn warning(&mut self, path: &str, message: String) {
+        self.push(path, Severity::Warning, message);
+    }
+
+    fn unknown_keys(&mut self, obj: &Map<String, Value>, known: &[&str], path: &str) {
+        for key in obj.keys() {
+            if !known.contains(&key.as_str()) {
+                self.warning(&format!("{}/{}", path, pointer_segment(key)), format!("unknown key '{}' is ignored", key));
+            }
+        }
+    }
+
+    fn check_text(&mut self, text: &str, path: &str) {
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '§' {
+                continue;
+            }
+            match chars.next() {
+                None => self.warning(path, "text ends with a dangling '§'".to_string()),
+                // The codes the renderer knows, so anything else shows up as text
+                Some(code) if code != '§' && !Style::default().apply(code) => {
+                    self.warning(path, format!("unknown format code '§{}'", code));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_string<'a>(&mut self, value: &'a Value, path: &str, what: &str) -> Option<&'a str> {
+        let s = value.as_str();
+        if s.is_none() {
+            self.error(path, format!("'{}' must be a string", what));
+        }
+        s
+    }
+
+    fn check_rawtext_object(&mut self, value: &Value, path: &str) {
+        let Some(obj) = value.as_object() else {
+            self.error(path, "expected an object with a 'rawtext' array".to_string());
+            return;
+        };
+        match obj.get("rawtext") {
+            Some(rawtext) => self.check_rawtext_array(rawtext, &format!("{}/rawtext", path)),
+            None => self.error(path, "missing 'rawtext'".to_string()),
+        }
+        self.unknown_keys(obj, &["rawtext"], path);
+    }
+
+    fn check_rawtext_array(&mut self, value: &Value, path: &str) {
+        let Some(arr) = value.as_array() else {
+            self.error(path, "'rawtext' must be an array".to_string());
+            return;
+        };
+        if arr.is_empty() {
+            self.warning(path, "'rawtext' is empty, nothing will be shown".to_string());
+        }
+        for (i, element) in arr.iter().enumerate() {
+            self.check_element(element, &format!("{}/{}", path, i));
+        }
+    }
+
+    fn check_element(&mut self, value: &Value, path: &str) {
+        let Some(obj) = value.as_object() else {
+            self.error(path, "rawtext element must be an object".to_string());
+            return;
+        };
+
+        let present: Vec<&str> = CONTENT_KEYS.iter().copied().filter(|k| obj.contains_key(*k)).collect();
+        match present.first() {
+            None => {
+                self.error(path, "element has none of 'text', 'translate', 'score', 'selector' or 'rawtext'".to_string());
+            }
+            Some(used) if present.len() > 1 => {
+                self.warning(path, format!("element has several of {:?}; only '{}' is used", present, used));
+            }
+            _ => {}
+        }
+
+        if let Some(text) = obj.get("text") {
+            if let Some(text) = self.check_string(text, &format!("{}/text", path), "text") {
+                self.check_text(text, &format!("{}/text", path));
+            }
+        }
+
+        if let Some(translate) = obj.get("translate") {
+            self.check_string(translate, &format!("{}/translate", path), "translate");
+        }
+        if let Some(with) = obj.get("with") {
+            let with_path = format!("{}/with", path);
+            if !obj.contains_key("translate") {
+                self.warning(&with_path, "'with' has no effect without 'translate'".to_string());
+            }
+            match with {
+                Value::Array(args) => {
+                    for (i, arg) in args.iter().enumerate() {
+                        if !arg.is_number() {
+                            self.check_string(arg, &format!("{}/{}", with_path, i), "with");
+                        }
+                    }
+                }
+                Value::Object(_) => self.check_rawtext_object(with, &with_path),
+                _ => self.error(&with_path, "'with' must be a string array or a rawtext object".to_string()),
+            }
+        }
+
+        if let Some(score) = obj.get("score") {
+            self.check_score(score, &format!("{}/score", path));
+        }
+
+        if let Some(selector) = obj.get("selector") {
+            let selector_path = format!("{}/selector", path);
+            if let Some(selector) = self.check_string(selector, &selector_path, "selector") {
+                if selector.starts_with('@') {
+                    if let Err(e) = Selector::parse(selector) {
+                        self.warning(&selector_path, e.to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(rawtext) = obj.get("rawtext") {
+            self.check_rawtext_array(rawtext, &format!("{}/rawtext", path));
+        }
+
+        self.unknown_keys(obj, &["text", "translate", "with", "score", "selector", "rawtext"], path);
+    }
+
+    fn check_score(&mut self, value: &Value, path: &str) {
+        let Some(score) = value.as_object() else {
+            self.error(path, "'score' must be an object".to_string());
+            return;
+        };
+        for key in ["name", "objective"] {
+            match score.get(key) {
+                Some(v) => {
+                    if let Some(s) = self.check_string(v, &format!("{}/{}", path, key), key) {
+                        if s.is_empty() {
+                            self.error(&format!("{}/{}", path, key), format!("'{}' must not be empty", key));
+                        }
+                    }
+                }
+                None => self.error(path, format!("'score' without '{}'", key)),
+            }
+        }
+        self.unknown_keys(score, &["name", "objective"], path);
+    }
+}
+
+pub fn validate_value(value: &Value) -> Vec<Diagnostic> {
+    let mut validator = Validator { diagnostics: Vec::new() };
+    // A bare JSON string is a message of plain text
+    match value.as_str() {
+        Some(text) => validator.check_text(text, ""),
+        None => validator.check_rawtext_object(value, ""),
+    }
+    validator.diagnostics
+}
+
+// Diagnostics for a tellraw JSON payload; an empty list means it is valid
+pub fn validate_tellraw(json: &str) -> Vec<Diagnostic> {
+    match serde_json::from_str::<Value>(json) {
+        Ok(value) => validate_value(&value),
+        Err(e) => vec![Diagnostic {
+            path: display_path(""),
+            severity: Severity::Error,
+            message: format!("Invalid JSON: {}", e),
+            line: Some(e.line()),
+            column: Some(e.column()),
+        }],
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(json: &str) -> Vec<(String, Severity)> {
+        validate_tellraw(json).into_iter().map(|d| (d.path, d.severity)).collect()
+    }
+
+    #[test]
+    fn valid_messages_have_no_diagnostics() {
+        assert!(validate_tellraw(r#"{"rawtext":[{"text":"§ahi"}]}"#).is_empty());
+        assert!(validate_tellraw(r#"{"rawtext":[{"translate":"a.%s","with":["x",3]}]}"#).is_empty());
+        assert!(validate_tellraw(r#"{"rawtext":[{"score":{"name":"*","objective":"kills"}}]}"#).is_empty());
+        assert!(validate_tellraw(r#""hello""#).is_empty());
+    }
+
+    #[test]
+    fn syntax_errors_report_position() {
+        let diagnostics = validate_tellraw("{\"rawtext\":\n[}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].path.as_str(), diagnostics[0].line), ("/", Some(2)));
+        assert!(has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn problems_are_reported_at_their_path() {
+        assert_eq!(paths(r#"{"text":"a"}"#), vec![("/".to_string(), Severity::Error), ("/text".to_string(), Severity::Warning)]);
+        assert_eq!(paths(r#"{"rawtext":[{"text":1}]}"#), vec![("/rawtext/0/text".to_string(), Severity::Error)]);
+        assert_eq!(
+            paths(r#"{"rawtext":[{"score":{"name":""}}]}"#),
+            vec![("/rawtext/0/score/name".to_string(), Severity::Error), ("/rawtext/0/score".to_string(), Severity::Error)]
+        );
+        assert_eq!(
+            paths(r#"{"rawtext":[{"text":"§zx","a/b":0}]}"#),
+            vec![("/rawtext/0/text".to_string(), Severity::Warning), ("/rawtext/0/a~1b".to_string(), Severity::Warning)]
+        );
+        assert!(!has_errors(&validate_tellraw(r#"{"rawtext":[]}"#)));
+    }
+
+    #[test]
+    fn validator_and_renderer_agree_on_format_codes() {
+        let font = crate::font::RuneFont::new("font_png");
+        let mut simulator = crate::render::TellRawSimulator::new(font.clone(), Default::default());
+        let plain = (simulator.render("X").width(), crate::align::get_line_width(&font, "X"));
+        // Quotes and backslashes would need escaping in the JSON below
+        for code in ('!'..='~').filter(|&c| c != '"' && c != '\\') {
+            let text = format!("X§{}", code);
+            let known = validate_tellraw(&format!(r#"{{"rawtext":[{{"text":"{}"}}]}}"#, text)).is_empty();
+            let rendered = (simulator.render(&text).width(), crate::align::get_line_width(&font, &text));
+            assert_eq!(known, rendered == plain, "§{}", code);
+        }
+    }
+}
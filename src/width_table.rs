@@ -0,0 +1,72 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::define::MISSING_GLYPH_WIDTH;
+use crate::font::RuneFont;
+
+// Advance widths measured from the glyph images, in logical units (32px cell) like
+// `SPACE_WIDTH`. Stored as runs of code points sharing a width to keep the JSON small.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WidthTable {
+    // Width of code points no run covers
+    pub default: u32,
+    // `[first, last, width]`, sorted and non-overlapping
+    pub runs: Vec<(u32, u32, u32)>,
+}
+
+impl WidthTable {
+    // Measure every BMP code point on the pages the font has, as `render` would draw it.
+    // The glyphs go through a cache of their own so the shared one is not flushed.
+    pub fn generate(font: &RuneFont) -> Self {
+        let font = font.with_private_cache();
+        let mut widths = Vec::new();
+        for page in (0..=0xFF).filter(|&page| font.page_origin(page).is_some()) {
+            // Surrogates are not characters
+            if (0xD8..=0xDF).contains(&page) {
+                continue;
+            }
+            for code in page * 256..(page + 1) * 256 {
+                let Some(ch) = char::from_u32(code) else { continue };
+                widths.push((code, font.logical_width(&ch.to_string(), 0) as u32));
+            }
+        }
+        // Basic Latin may come from `default8.png` without `glyph_00.png`
+        if font.page_origin(0).is_none() {
+            for code in 0x20..0x7F {
+                widths.push((code, font.logical_width(&char::from_u32(code).unwrap().to_string(), 0) as u32));
+            }
+            widths.sort();
+        }
+        Self::from_widths(MISSING_GLYPH_WIDTH as u32, widths)
+    }
+
+    // `widths` sorted by code point
+    pub fn from_widths(default: u32, widths: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        let mut runs: Vec<(u32, u32, u32)> = Vec::new();
+        for (code, width) in widths {
+            match runs.last_mut() {
+                Some((_, last, w)) if *last + 1 == code && *w == width => *last = code,
+                _ => runs.push((code, code, width)),
+            }
+        }
+        Self { default, runs }
+    }
+
+    pub fn width(&self, code: u32) -> u32 {
+        let i = self.runs.partition_point(|&(_, last, _)| last < code);
+        match self.runs.get(i) {
+            Some(&(first, _, width)) if first <= code => width,
+            _ => self.default,
+        }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)
+    }
+}
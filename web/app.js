@@ -12,6 +12,28 @@ function showError(message) {
     }, 5000);
 }
 
+// Build /api/render request body, including optional fixture and substitutions
+function buildRenderBody(formData) {
+    const body = {
+        mode: formData.get('mode'),
+        content: formData.get('content').trim()
+    };
+    const fixture = (formData.get('fixture') || '').trim();
+    if (fixture) body.fixture = fixture;
+    const substitutions = (formData.get('substitutions') || '').trim();
+    if (substitutions) {
+        let parsed;
+        try {
+            parsed = JSON.parse(substitutions);
+        } catch (e) {
+            throw new Error('Substitutions must be valid JSON');
+        }
+        if (parsed.scores) body.scores = parsed.scores;
+        if (parsed.selectors) body.selectors = parsed.selectors;
+    }
+    return body;
+}
+
 // Render function
 async function renderText(formData) {
     try {
@@ -21,10 +43,7 @@ async function renderText(formData) {
                 'Content-Type': 'application/json',
                 'Accept': 'image/png, application/json'
             },
-            body: JSON.stringify({
-                mode: formData.get('mode'),
-                content: formData.get('content').trim()
-            })
+            body: JSON.stringify(buildRenderBody(formData))
         });
 
         if (!response.ok) {